@@ -1,5 +1,5 @@
 /// A regular expression. The top-level type.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Expr {
     /// Just this expression
     Atom(Atom),
@@ -11,34 +11,307 @@ pub enum Expr {
     Or(Vec<Expr>),
     /// Zero or one
     ZeroOrOne(Vec<Expr>),
+    /// Zero or one, non-greedy
+    ZeroOrOneReluctant(Vec<Expr>),
+    /// Zero or one, possessive
+    ZeroOrOnePossessive(Vec<Expr>),
     /// 0+, greedy
     ZeroOrMore(Vec<Expr>),
     /// 0+, non-greedy
     ZeroOrMoreReluctant(Vec<Expr>),
+    /// 0+, possessive
+    ZeroOrMorePossessive(Vec<Expr>),
     /// 1+, greedy
     OneOrMore(Vec<Expr>),
     /// 1+, non-greedy
     OneOrMoreReluctant(Vec<Expr>),
+    /// 1+, possessive
+    OneOrMorePossessive(Vec<Expr>),
     /// Match exactly N times
     Exactly(u32, Vec<Expr>),
+    /// Match exactly N times, possessive
+    ExactlyPossessive(u32, Vec<Expr>),
     /// Match at least N times
     AtLeast(u32, Vec<Expr>),
+    /// Match at least N times, non-greedy
+    AtLeastReluctant(u32, Vec<Expr>),
+    /// Match at least N times, possessive
+    AtLeastPossessive(u32, Vec<Expr>),
     /// Match between N and M times
     Between(u32, u32, Vec<Expr>),
-    /// Not the atom
-    Not(Atom),
-    /// Any character from the sets
-    Any(Vec<Atom>),
+    /// Match between N and M times, non-greedy
+    BetweenReluctant(u32, u32, Vec<Expr>),
+    /// Match between N and M times, possessive
+    BetweenPossessive(u32, u32, Vec<Expr>),
+    /// Any character not in the set
+    Not(Vec<SetItem>),
+    /// Any character from the set
+    Any(Vec<SetItem>),
+    /// Any character in all of the character sets
+    Intersection(Vec<Expr>),
+    /// Any character in the first character set but none of the others
+    Difference(Vec<Expr>),
+    /// A group which does not backtrack once matched
+    Atomic(Vec<Expr>),
     /// A capture group
     Group(Vec<Expr>),
     /// A capture group numbered N
     GroupN(u32, Vec<Expr>),
-    /// The text captured in group N
-    BackRef(String),
+    /// A capture group with a name
+    GroupNamed(String, Vec<Expr>),
+    /// The text captured by a group
+    BackRef(GroupRef),
+    /// The whole expression again, recursively
+    Recurse,
+    /// The expression of a group again, as a subroutine
+    Call(GroupRef),
+    /// The first expression if the group has matched, otherwise the
+    /// second one, if any
+    IfGroup(GroupRef, Vec<Expr>),
+    /// Expressions matched with a flag in effect
+    Scoped(Flag, Vec<Expr>),
+    /// A reference to a named definition
+    Reference(String),
+    /// A one-character symbol, which is a reference if a definition
+    /// by that name is in scope, and otherwise the character itself
+    Symbol(char),
+    /// Definitions which are in scope in the expression
+    Let(Vec<Definition>, Box<Expr>),
+    /// A call of a parameterized definition with arguments, and the
+    /// source text of the call
+    MacroCall(String, Vec<Expr>, String),
+    /// Regex text inserted as is, either for any output format or
+    /// for the named one
+    Raw(Vec<(Option<String>, String)>),
+}
+
+/// A named expression, which can be referenced by name
+#[derive(Clone, Debug)]
+pub struct Definition {
+    /// The name
+    pub name: String,
+    /// The names of the parameters, if any
+    pub params: Vec<String>,
+    /// The expression the name stands for
+    pub expr: Expr,
+}
+
+/// A matching flag which can be applied to part of an expression
+#[derive(Clone, Debug)]
+pub enum Flag {
+    /// Ignore case
+    CaseInsensitive,
+    /// Do not ignore case
+    CaseSensitive,
+    /// Line anchors match at every line
+    Multiline,
+    /// The wildcard also matches newlines
+    DotAll,
+}
+
+/// A reference to a capture group
+#[derive(Clone, Debug)]
+pub enum GroupRef {
+    /// The group with this number
+    Number(u32),
+    /// The group with this name
+    Name(String),
+    /// The group this many groups after, or if negative before, the
+    /// reference
+    Relative(i32),
+}
+
+impl Expr {
+    /// The minimum and maximum number of characters this expression
+    /// can match. The maximum is `None` if it is unbounded.
+    pub fn width(&self) -> (u32, Option<u32>) {
+        match self {
+            Expr::Atom(Atom::String(s)) => {
+                let n = s.chars().count() as u32;
+                (n, Some(n))
+            }
+            Expr::Atom(Atom::Grapheme) => (1, None),
+            Expr::Atom(Atom::NewlineSequence) => (1, Some(2)),
+            Expr::Atom(_)
+            | Expr::Not(_)
+            | Expr::Any(_)
+            | Expr::Intersection(_)
+            | Expr::Difference(_) => (1, Some(1)),
+            Expr::Assertion(_) => (0, Some(0)),
+            Expr::Seq(exprs)
+            | Expr::Atomic(exprs)
+            | Expr::Group(exprs)
+            | Expr::GroupN(_, exprs)
+            | Expr::GroupNamed(_, exprs)
+            | Expr::Scoped(_, exprs) => seq_width(exprs),
+            Expr::Or(exprs) => alternatives_width(exprs.iter().map(Expr::width)),
+            Expr::IfGroup(_, exprs) => match exprs.as_slice() {
+                [then] => alternatives_width([then.width(), (0, Some(0))].into_iter()),
+                _ => alternatives_width(exprs.iter().map(Expr::width)),
+            },
+            Expr::ZeroOrOne(exprs)
+            | Expr::ZeroOrOneReluctant(exprs)
+            | Expr::ZeroOrOnePossessive(exprs) => repeat_width(seq_width(exprs), 0, Some(1)),
+            Expr::ZeroOrMore(exprs)
+            | Expr::ZeroOrMoreReluctant(exprs)
+            | Expr::ZeroOrMorePossessive(exprs) => repeat_width(seq_width(exprs), 0, None),
+            Expr::OneOrMore(exprs)
+            | Expr::OneOrMoreReluctant(exprs)
+            | Expr::OneOrMorePossessive(exprs) => repeat_width(seq_width(exprs), 1, None),
+            Expr::Exactly(n, exprs) | Expr::ExactlyPossessive(n, exprs) => {
+                repeat_width(seq_width(exprs), *n, Some(*n))
+            }
+            Expr::AtLeast(n, exprs)
+            | Expr::AtLeastReluctant(n, exprs)
+            | Expr::AtLeastPossessive(n, exprs) => repeat_width(seq_width(exprs), *n, None),
+            Expr::Between(n, m, exprs)
+            | Expr::BetweenReluctant(n, m, exprs)
+            | Expr::BetweenPossessive(n, m, exprs) => repeat_width(seq_width(exprs), *n, Some(*m)),
+            Expr::Let(_, expr) => expr.width(),
+            Expr::BackRef(_)
+            | Expr::Recurse
+            | Expr::Call(_)
+            | Expr::Reference(_)
+            | Expr::Symbol(_)
+            | Expr::MacroCall(_, _, _)
+            | Expr::Raw(_) => (0, None),
+        }
+    }
+}
+
+impl Expr {
+    /// The sub-expressions directly contained in this expression.
+    pub fn children(&self) -> &[Expr] {
+        match self {
+            Expr::Seq(exprs)
+            | Expr::Or(exprs)
+            | Expr::ZeroOrOne(exprs)
+            | Expr::ZeroOrOneReluctant(exprs)
+            | Expr::ZeroOrOnePossessive(exprs)
+            | Expr::ZeroOrMore(exprs)
+            | Expr::ZeroOrMoreReluctant(exprs)
+            | Expr::ZeroOrMorePossessive(exprs)
+            | Expr::OneOrMore(exprs)
+            | Expr::OneOrMoreReluctant(exprs)
+            | Expr::OneOrMorePossessive(exprs)
+            | Expr::Exactly(_, exprs)
+            | Expr::ExactlyPossessive(_, exprs)
+            | Expr::AtLeast(_, exprs)
+            | Expr::AtLeastReluctant(_, exprs)
+            | Expr::AtLeastPossessive(_, exprs)
+            | Expr::Between(_, _, exprs)
+            | Expr::BetweenReluctant(_, _, exprs)
+            | Expr::BetweenPossessive(_, _, exprs)
+            | Expr::Atomic(exprs)
+            | Expr::Group(exprs)
+            | Expr::GroupN(_, exprs)
+            | Expr::GroupNamed(_, exprs)
+            | Expr::Scoped(_, exprs)
+            | Expr::MacroCall(_, exprs, _)
+            | Expr::IfGroup(_, exprs)
+            | Expr::Intersection(exprs)
+            | Expr::Difference(exprs)
+            | Expr::Assertion(Assertion::LookAhead(exprs))
+            | Expr::Assertion(Assertion::NegativeLookAhead(exprs))
+            | Expr::Assertion(Assertion::LookBehind(exprs))
+            | Expr::Assertion(Assertion::NegativeLookBehind(exprs)) => exprs,
+            Expr::Let(_, expr) => std::slice::from_ref(expr),
+            Expr::Atom(_)
+            | Expr::Assertion(_)
+            | Expr::Not(_)
+            | Expr::Any(_)
+            | Expr::BackRef(_)
+            | Expr::Recurse
+            | Expr::Call(_)
+            | Expr::Reference(_)
+            | Expr::Symbol(_)
+            | Expr::Raw(_) => &[],
+        }
+    }
+
+    /// Mutable access to the sub-expressions directly contained in
+    /// this expression.
+    pub fn children_mut(&mut self) -> &mut [Expr] {
+        match self {
+            Expr::Seq(exprs)
+            | Expr::Or(exprs)
+            | Expr::ZeroOrOne(exprs)
+            | Expr::ZeroOrOneReluctant(exprs)
+            | Expr::ZeroOrOnePossessive(exprs)
+            | Expr::ZeroOrMore(exprs)
+            | Expr::ZeroOrMoreReluctant(exprs)
+            | Expr::ZeroOrMorePossessive(exprs)
+            | Expr::OneOrMore(exprs)
+            | Expr::OneOrMoreReluctant(exprs)
+            | Expr::OneOrMorePossessive(exprs)
+            | Expr::Exactly(_, exprs)
+            | Expr::ExactlyPossessive(_, exprs)
+            | Expr::AtLeast(_, exprs)
+            | Expr::AtLeastReluctant(_, exprs)
+            | Expr::AtLeastPossessive(_, exprs)
+            | Expr::Between(_, _, exprs)
+            | Expr::BetweenReluctant(_, _, exprs)
+            | Expr::BetweenPossessive(_, _, exprs)
+            | Expr::Atomic(exprs)
+            | Expr::Group(exprs)
+            | Expr::GroupN(_, exprs)
+            | Expr::GroupNamed(_, exprs)
+            | Expr::Scoped(_, exprs)
+            | Expr::MacroCall(_, exprs, _)
+            | Expr::IfGroup(_, exprs)
+            | Expr::Intersection(exprs)
+            | Expr::Difference(exprs)
+            | Expr::Assertion(Assertion::LookAhead(exprs))
+            | Expr::Assertion(Assertion::NegativeLookAhead(exprs))
+            | Expr::Assertion(Assertion::LookBehind(exprs))
+            | Expr::Assertion(Assertion::NegativeLookBehind(exprs)) => exprs,
+            Expr::Let(_, expr) => std::slice::from_mut(expr),
+            Expr::Atom(_)
+            | Expr::Assertion(_)
+            | Expr::Not(_)
+            | Expr::Any(_)
+            | Expr::BackRef(_)
+            | Expr::Recurse
+            | Expr::Call(_)
+            | Expr::Reference(_)
+            | Expr::Symbol(_)
+            | Expr::Raw(_) => &mut [],
+        }
+    }
+}
+
+fn alternatives_width(widths: impl Iterator<Item = (u32, Option<u32>)>) -> (u32, Option<u32>) {
+    widths
+        .reduce(|(min_a, max_a), (min_b, max_b)| {
+            (min_a.min(min_b), max_a.zip(max_b).map(|(a, b)| a.max(b)))
+        })
+        .unwrap_or((0, Some(0)))
+}
+
+/// The combined width of expressions matched in sequence.
+pub fn seq_width(exprs: &[Expr]) -> (u32, Option<u32>) {
+    exprs
+        .iter()
+        .map(Expr::width)
+        .fold((0, Some(0)), |(min_a, max_a), (min_b, max_b)| {
+            (
+                min_a.saturating_add(min_b),
+                max_a.zip(max_b).map(|(a, b)| a.saturating_add(b)),
+            )
+        })
+}
+
+fn repeat_width((min, max): (u32, Option<u32>), n: u32, m: Option<u32>) -> (u32, Option<u32>) {
+    let max = match (max, m) {
+        (Some(0), _) => Some(0),
+        (Some(max), Some(m)) => Some(max.saturating_mul(m)),
+        _ => None,
+    };
+    (min.saturating_mul(n), max)
 }
 
 /// A single static element
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Atom {
     /// A single character
     Char(char),
@@ -46,23 +319,77 @@ pub enum Atom {
     String(String),
     /// A character class
     CharClass(CharClass),
+    /// Any character except a newline
+    NotNewline,
+    /// Any character at all
+    AnyChar,
+    /// A user-perceived character, e.g. a letter with combining marks
+    Grapheme,
+    /// Any line break, including `\r\n`
+    NewlineSequence,
+}
+
+/// A member of a character set
+#[derive(Clone, Debug)]
+pub enum SetItem {
+    /// A single character
+    Char(char),
+    /// All characters between the two, inclusive
+    Range(char, char),
+    /// A character class
+    CharClass(CharClass),
+    /// A one-character symbol, resolved like `Expr::Symbol`
+    Symbol(char),
+    /// A range with a symbol at either end
+    SymbolRange(RangeEnd, RangeEnd),
+}
+
+/// An end of a range in a character set
+#[derive(Clone, Debug)]
+pub enum RangeEnd {
+    /// A literal character
+    Char(char),
+    /// A one-character symbol, resolved like `Expr::Symbol`
+    Symbol(char),
 }
 
 /// Zero-width assertion, e.g. line end
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Assertion {
     /// Start of a line or input
     LineStart,
     /// End of a line or input
     LineEnd,
+    /// Start of the input
+    StringStart,
+    /// End of the input
+    StringEnd,
+    /// End of the input, or before a newline at the end
+    StringEndBeforeNewline,
     /// Beginning or end of a word
     WordBoundary,
     /// Not the beginning or end of a word
     NotWordBoundary,
+    /// Beginning of a word
+    WordStart,
+    /// End of a word
+    WordEnd,
+    /// Beginning of a symbol, which is made of word characters and `$`
+    SymbolStart,
+    /// End of a symbol
+    SymbolEnd,
+    /// Followed by the expressions
+    LookAhead(Vec<Expr>),
+    /// Not followed by the expressions
+    NegativeLookAhead(Vec<Expr>),
+    /// Preceded by the expressions
+    LookBehind(Vec<Expr>),
+    /// Not preceded by the expressions
+    NegativeLookBehind(Vec<Expr>),
 }
 
 /// A character class
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum CharClass {
     /// Any whitespace character
     Whitespace,
@@ -80,4 +407,16 @@ pub enum CharClass {
     UpperCase,
     /// A "word" character
     Word,
+    /// Any character that is not whitespace
+    NotWhitespace,
+    /// Any character that is not a decimal digit
+    NotDigit,
+    /// Any character that is not a "word" character
+    NotWord,
+    /// A Unicode general category, by its abbreviation, e.g. `Lu`
+    Category(&'static str),
+    /// A Unicode script, e.g. `Greek`
+    Script(&'static str),
+    /// A Unicode block, as its range of code points
+    Block(char, char),
 }
@@ -2,13 +2,16 @@ use anyhow::Result;
 
 use crate::output::Output;
 use crate::parser::parse;
+use crate::resolve::resolve;
 
 pub mod expr;
 pub mod output;
 pub mod parser;
+pub mod resolve;
 
 pub fn convert(input: &str, output: &dyn Output) -> Result<String> {
     if let Ok((_, expr)) = parse(input) {
+        let expr = resolve(&expr)?;
         Ok(output.output(&expr)?)
     } else {
         Err(anyhow::anyhow!("Failed to parse input"))
@@ -5,6 +5,8 @@ use rx::convert;
 use rx::output::Output;
 use rx::output::{
     debug::DebugOutput, javascript::JavascriptOutput, pcre::PCREOutput, pcre2::PCRE2Output,
+    posix_bre::PosixBreOutput, posix_ere::PosixEreOutput, python::PythonOutput, re2::RE2Output,
+    rust_regex::RustRegexOutput,
 };
 
 #[derive(Parser)]
@@ -13,6 +15,24 @@ struct Args {
     /// Output format
     #[clap(short, long, value_enum)]
     output: OutputFormat,
+    /// Assume the pattern is used with the `u` flag (Javascript only)
+    #[clap(short, long)]
+    unicode: bool,
+    /// Assume the pattern is used with the `s` flag (Javascript only)
+    #[clap(short = 's', long)]
+    dot_all: bool,
+    /// Assume the pattern is used with the `m` flag (Javascript only)
+    #[clap(short, long)]
+    multiline: bool,
+    /// Assume the pattern is used with the `v` flag (Javascript only)
+    #[clap(short = 'v', long)]
+    unicode_sets: bool,
+    /// Output a raw string literal (Python only)
+    #[clap(short, long)]
+    raw_string: bool,
+    /// Use GNU extensions (POSIX BRE only)
+    #[clap(short, long)]
+    gnu: bool,
     /// The rx expression
     expression: String,
 }
@@ -22,11 +42,21 @@ enum OutputFormat {
     /// An unstable, human-readable format for debugging parsing.
     Debug,
     /// Perl Compatible Regular Expression
-    PCRE,
+    Pcre,
     /// Perl Compatible Regular Expression, version 2
-    PCRE2,
+    Pcre2,
     /// Javascript
-    JS,
+    Js,
+    /// The Rust `regex` crate
+    Rust,
+    /// RE2, as also used by Go's `regexp`
+    Re2,
+    /// Python's `re` module
+    Python,
+    /// POSIX extended regular expressions, as used by `grep -E` and awk
+    PosixEre,
+    /// POSIX basic regular expressions, as used by `sed` and `grep`
+    PosixBre,
 }
 
 pub fn main() -> Result<()> {
@@ -34,9 +64,21 @@ pub fn main() -> Result<()> {
 
     let out: &dyn Output = match args.output {
         OutputFormat::Debug => &DebugOutput {},
-        OutputFormat::PCRE => &PCREOutput {},
-        OutputFormat::PCRE2 => &PCRE2Output {},
-        OutputFormat::JS => &JavascriptOutput {},
+        OutputFormat::Pcre => &PCREOutput {},
+        OutputFormat::Pcre2 => &PCRE2Output {},
+        OutputFormat::Js => &JavascriptOutput {
+            unicode: args.unicode,
+            dot_all: args.dot_all,
+            multiline: args.multiline,
+            unicode_sets: args.unicode_sets,
+        },
+        OutputFormat::Rust => &RustRegexOutput {},
+        OutputFormat::Re2 => &RE2Output {},
+        OutputFormat::Python => &PythonOutput {
+            raw_string: args.raw_string,
+        },
+        OutputFormat::PosixEre => &PosixEreOutput {},
+        OutputFormat::PosixBre => &PosixBreOutput { gnu: args.gnu },
     };
 
     print!("{}", convert(&args.expression, out)?);
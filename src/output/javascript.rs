@@ -1,11 +1,24 @@
-use crate::expr::{Assertion, Atom, CharClass, Expr};
-use crate::output::{Output, OutputError};
+use crate::expr::{Assertion, Atom, CharClass, Expr, Flag, GroupRef, SetItem};
+use crate::output::set::{ascii_class_set, eval_set, set_operation_lookarounds, CharSet};
+use crate::output::{check_group_refs, check_no_recursion, raw_variant, Output, OutputError};
 
 #[derive(Copy, Clone, Default)]
-pub struct JavascriptOutput {}
+pub struct JavascriptOutput {
+    /// Whether the pattern will be used with the `u` flag.
+    pub unicode: bool,
+    /// Whether the pattern will be used with the `s` flag.
+    pub dot_all: bool,
+    /// Whether the pattern will be used with the `m` flag.
+    pub multiline: bool,
+    /// Whether the pattern will be used with the `v` flag, which
+    /// implies everything the `u` flag does.
+    pub unicode_sets: bool,
+}
 
 impl Output for JavascriptOutput {
     fn output(&self, expr: &Expr) -> Result<String, OutputError> {
+        check_no_recursion(expr)?;
+        check_group_refs(expr)?;
         self.output_expr(expr)
     }
 }
@@ -14,145 +27,258 @@ impl JavascriptOutput {
     fn output_expr(&self, expr: &Expr) -> Result<String, OutputError> {
         match expr {
             Expr::Atom(c) => self.output_atom(c),
-            Expr::Seq(exprs) => {
-                let mut s = String::new();
-                for e in exprs {
-                    // NB In a sequence, we need to avoid mushing
-                    // together several tokens that will then get
-                    // affected by a postfix modifier.
-                    match e {
-                        Expr::Atom(_) => s.push_str(&self.output_expr(e)?),
-                        Expr::Assertion(_) => s.push_str(&self.output_expr(e)?),
-                        _ => s.push_str(&format!("(?:{})", self.output_expr(e)?)),
-                    }
-                }
-                Ok(s)
-            }
+            Expr::Seq(exprs) => self.output_seq(exprs),
             Expr::Or(exprs) if exprs.len() == 1 => self.output_expr(exprs.first().expect("")),
             Expr::Or(exprs) => {
-                let mut s = format!("{}|", self.output_expr(exprs.first().expect(""))?);
+                let mut s = self.output_expr(exprs.first().expect(""))?;
                 for e in exprs[1..].iter() {
+                    s.push('|');
                     s.push_str(&self.output_expr(e)?);
                 }
                 Ok(s)
             }
-            Expr::ZeroOrOne(exprs) => {
-                let mut s = String::from("(?:");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
-                }
-                s.push('?');
-                Ok(s)
+            Expr::ZeroOrOne(exprs) => self.output_quantified(exprs, "?"),
+            Expr::ZeroOrOneReluctant(exprs) => self.output_quantified(exprs, "??"),
+            Expr::ZeroOrMore(exprs) => self.output_quantified(exprs, "*"),
+            Expr::ZeroOrMoreReluctant(exprs) => self.output_quantified(exprs, "*?"),
+            Expr::OneOrMore(exprs) => self.output_quantified(exprs, "+"),
+            Expr::OneOrMoreReluctant(exprs) => self.output_quantified(exprs, "+?"),
+            Expr::Exactly(n, exprs) => self.output_counted(exprs, &format!("{{{n}}}")),
+            Expr::AtLeast(n, exprs) => self.output_counted(exprs, &format!("{{{n},}}")),
+            Expr::AtLeastReluctant(n, exprs) => self.output_counted(exprs, &format!("{{{n},}}?")),
+            Expr::Between(n, m, exprs) => self.output_counted(exprs, &format!("{{{n},{m}}}")),
+            Expr::BetweenReluctant(n, m, exprs) => {
+                self.output_counted(exprs, &format!("{{{n},{m}}}?"))
             }
-            Expr::ZeroOrMore(exprs) if exprs.len() == 1 => {
-                Ok(format!("{}*", self.output_expr(exprs.first().unwrap())?))
+            Expr::ZeroOrOnePossessive(_)
+            | Expr::ZeroOrMorePossessive(_)
+            | Expr::OneOrMorePossessive(_)
+            | Expr::ExactlyPossessive(_, _)
+            | Expr::AtLeastPossessive(_, _)
+            | Expr::BetweenPossessive(_, _, _) => {
+                Err(OutputError::FeatureNotSupported("possessive quantifiers"))
             }
-            Expr::ZeroOrMore(exprs) => {
-                let mut s = String::from("(?:");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
-                }
-                s.push_str(")*");
-                Ok(s)
-            }
-            Expr::ZeroOrMoreReluctant(exprs) if exprs.len() == 1 => {
-                Ok(format!("{}*?", self.output_expr(exprs.first().unwrap())?))
-            }
-            Expr::ZeroOrMoreReluctant(exprs) => {
-                let mut s = String::from("(?:");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
+            Expr::Atomic(_) => Err(OutputError::FeatureNotSupported("atomic groups")),
+            Expr::Assertion(assertion) => self.output_assertion(assertion),
+            Expr::Intersection(_) | Expr::Difference(_) => self.output_set_operation(expr),
+            Expr::Any(items) => {
+                let mut s = String::from("[");
+                for item in items {
+                    s.push_str(&self.output_set_item(item)?);
                 }
-                s.push_str(")*?");
+                s.push(']');
                 Ok(s)
             }
-            Expr::OneOrMore(exprs) if exprs.len() == 1 => {
-                Ok(format!("{}+", self.output_expr(exprs.first().unwrap())?))
-            }
-            Expr::OneOrMore(exprs) => {
-                let mut s = String::from("(?:");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
+            Expr::Not(items) => {
+                let mut s = String::from("[^");
+                for item in items {
+                    s.push_str(&self.output_set_item(item)?);
                 }
-                s.push_str(")+");
+                s.push(']');
                 Ok(s)
             }
-            Expr::OneOrMoreReluctant(exprs) if exprs.len() == 1 => {
-                Ok(format!("{}+?", self.output_expr(exprs.first().unwrap())?))
+            Expr::Group(exprs) => Ok(format!("({})", self.output_seq(exprs)?)),
+            Expr::GroupN(_, _) => Err(OutputError::FeatureNotSupported("numbered capture groups")),
+            Expr::GroupNamed(name, exprs) => Ok(format!("(?<{name}>{})", self.output_seq(exprs)?)),
+            Expr::BackRef(GroupRef::Number(n)) => Ok(format!("\\{n}")),
+            Expr::BackRef(GroupRef::Name(name)) => Ok(format!("\\k<{name}>")),
+            Expr::BackRef(GroupRef::Relative(_)) => {
+                Err(OutputError::FeatureNotSupported("relative backrefs"))
             }
-            Expr::OneOrMoreReluctant(exprs) => {
-                let mut s = String::from("(?:");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
-                }
-                s.push_str(")+?");
-                Ok(s)
+            Expr::Recurse | Expr::Call(_) => Err(OutputError::FeatureNotSupported("recursion")),
+            Expr::IfGroup(_, _) => Err(OutputError::FeatureNotSupported("conditional groups")),
+            Expr::Raw(variants) => Ok(raw_variant(variants, "js")?.to_string()),
+            Expr::Reference(_) | Expr::Symbol(_) | Expr::Let(_, _) | Expr::MacroCall(_, _, _) => {
+                Err(OutputError::FeatureNotSupported("unresolved definitions"))
             }
-            Expr::Exactly(n, exprs) => {
-                let mut s = String::from("(?:");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
+            // Javascript has no scoped modifiers, so we rewrite the
+            // expressions to behave as if the flag was set.
+            Expr::Scoped(Flag::CaseInsensitive, exprs) => {
+                let mut exprs = exprs.clone();
+                for e in exprs.iter_mut() {
+                    fold_case(e)?;
                 }
-                s.push_str(&format!("){{{n}}}"));
-                Ok(s)
+                self.output_seq(&exprs)
             }
-            Expr::AtLeast(n, exprs) => {
-                let mut s = String::from("(?:");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
+            Expr::Scoped(Flag::Multiline, exprs) if !self.multiline => {
+                let mut exprs = exprs.clone();
+                for e in exprs.iter_mut() {
+                    emulate_multiline(e);
                 }
-                s.push_str(&format!("){{{n},}}"));
-                Ok(s)
+                self.output_seq(&exprs)
             }
-            Expr::Between(n, m, exprs) => {
-                let mut s = String::from("(?:");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
+            // Our wildcards are explicit about newlines already.
+            Expr::Scoped(_, exprs) => self.output_seq(exprs),
+        }
+    }
+
+    fn output_seq(&self, exprs: &[Expr]) -> Result<String, OutputError> {
+        if let [e] = exprs {
+            return self.output_expr(e);
+        }
+        self.output_seq_items(exprs)
+    }
+
+    fn output_seq_items(&self, exprs: &[Expr]) -> Result<String, OutputError> {
+        let mut s = String::new();
+        for e in exprs {
+            // NB In a sequence, we need to avoid mushing
+            // together several tokens that will then get
+            // affected by a postfix modifier.
+            match e {
+                Expr::Atom(_) => s.push_str(&self.output_expr(e)?),
+                Expr::Assertion(_) => s.push_str(&self.output_expr(e)?),
+                Expr::Any(_) | Expr::Not(_) | Expr::Intersection(_) | Expr::Difference(_) => {
+                    s.push_str(&self.output_expr(e)?)
                 }
-                s.push_str(&format!("){{{n},{m}}}"));
-                Ok(s)
+                Expr::Seq(exprs) => s.push_str(&self.output_seq_items(exprs)?),
+                _ => s.push_str(&format!("(?:{})", self.output_expr(e)?)),
             }
-            Expr::Assertion(assertion) => self.output_assertion(assertion),
-            Expr::Any(atoms) => {
-                let mut s = String::from("[");
-                for a in atoms {
-                    if let Atom::CharClass(c) = a {
-                        s.push_str(&self.output_char_class(c)?);
-                    } else {
-                        s.push_str(&self.output_atom(a)?);
-                    }
+        }
+        Ok(s)
+    }
+
+    fn output_set_operation(&self, expr: &Expr) -> Result<String, OutputError> {
+        let (operator, operands) = match expr {
+            Expr::Intersection(operands) => ("&&", operands),
+            Expr::Difference(operands) => ("--", operands),
+            _ => return self.output_expr(expr),
+        };
+        if self.unicode_sets {
+            let mut s = String::from("[");
+            for (idx, operand) in operands.iter().enumerate() {
+                if idx > 0 {
+                    s.push_str(operator);
                 }
-                s.push(']');
-                Ok(s)
-            }
-            Expr::Not(Atom::CharClass(class)) => {
-                Ok(format!("[^{}]", self.output_char_class(class)?))
+                s.push_str(&self.output_set_operand(operand)?);
             }
-            Expr::Not(atom) => Ok(format!("[^{}]", self.output_atom(atom)?)),
-            Expr::Group(exprs) => {
-                let mut s = String::from("(");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
-                }
-                s.push(')');
-                Ok(s)
+            s.push(']');
+            return Ok(s);
+        }
+        match eval_set(expr, &class_set)? {
+            Some(set) => self.output_expr(&set.to_expr()),
+            None => self.output_seq_items(&set_operation_lookarounds(expr)),
+        }
+    }
+
+    /// A set operand, which has to be a nested set with the `v` flag.
+    fn output_set_operand(&self, expr: &Expr) -> Result<String, OutputError> {
+        match expr {
+            Expr::Any(_)
+            | Expr::Not(_)
+            | Expr::Intersection(_)
+            | Expr::Difference(_)
+            | Expr::Atom(Atom::CharClass(_) | Atom::NotNewline) => self.output_expr(expr),
+            Expr::Atom(Atom::Char(c)) => Ok(format!("[{}]", self.output_set_char(*c)?)),
+            Expr::Atom(Atom::String(s)) if s.chars().count() == 1 => Ok(format!(
+                "[{}]",
+                self.output_set_char(s.chars().next().expect("one char"))?
+            )),
+            Expr::Atom(Atom::AnyChar) => Ok("[\\s\\S]".to_string()),
+            _ => Err(OutputError::NotACharSet),
+        }
+    }
+
+    fn output_quantified(&self, exprs: &[Expr], quantifier: &str) -> Result<String, OutputError> {
+        match exprs {
+            // Single characters can take a quantifier directly, but
+            // anything longer needs to be grouped.
+            [Expr::Atom(Atom::String(s))] if s.chars().count() != 1 || !self.is_code_unit(s) => {
+                Ok(format!("(?:{}){quantifier}", self.output_seq(exprs)?))
             }
-            Expr::GroupN(_, _) => Err(OutputError::FeatureNotSupported("named capture groups")),
-            Expr::BackRef(n) => {
-                if n.chars().all(|c| c.is_ascii_digit()) {
-                    Ok(format!("\\{n}"))
-                } else {
-                    Err(OutputError::FeatureNotSupported("non-numerical backrefs"))
-                }
+            [Expr::Atom(Atom::Char(c))] if !self.is_code_unit(&c.to_string()) => {
+                Ok(format!("(?:{}){quantifier}", self.output_seq(exprs)?))
             }
+            [Expr::Atom(atom)] => Ok(format!("{}{quantifier}", self.output_atom(atom)?)),
+            _ => Ok(format!("(?:{}){quantifier}", self.output_seq(exprs)?)),
         }
     }
 
+    fn is_unicode(&self) -> bool {
+        self.unicode || self.unicode_sets
+    }
+
+    /// Whether the text is a single code unit to the engine. Without
+    /// the u flag, astral characters are two.
+    fn is_code_unit(&self, s: &str) -> bool {
+        self.is_unicode() || s.encode_utf16().count() == 1
+    }
+
+    fn output_counted(&self, exprs: &[Expr], quantifier: &str) -> Result<String, OutputError> {
+        Ok(format!("(?:{}){quantifier}", self.output_seq(exprs)?))
+    }
+
     fn output_atom(&self, atom: &Atom) -> Result<String, OutputError> {
         match atom {
-            Atom::Char(c) => Ok(format!("{c}")),
-            Atom::String(s) => Ok(s.clone()),
+            Atom::Char(c) => Ok(self.output_char(*c)),
+            Atom::String(s) => Ok(s.chars().map(|c| self.output_char(c)).collect()),
             Atom::CharClass(class) => Ok(format!("[{}]", self.output_char_class(class)?)),
+            Atom::NotNewline => Ok("[^\\n]".to_string()),
+            Atom::AnyChar if self.dot_all => Ok(".".to_string()),
+            Atom::AnyChar => Ok("[\\s\\S]".to_string()),
+            // Only combining marks are kept together, unlike with
+            // extended grapheme clusters.
+            Atom::Grapheme if self.is_unicode() => Ok("(?:\\r\\n|\\P{M}\\p{M}*)".to_string()),
+            Atom::Grapheme => Err(OutputError::FeatureNotSupported(
+                "graphemes without the u flag",
+            )),
+            Atom::NewlineSequence => {
+                Ok("(?:\\r\\n|[\\n\\v\\f\\r\\u0085\\u2028\\u2029])".to_string())
+            }
+        }
+    }
+
+    fn output_char(&self, c: char) -> String {
+        match c {
+            '\\' | '^' | '$' | '.' | '|' | '?' | '*' | '+' | '(' | ')' | '[' | ']' | '{' | '}' => {
+                format!("\\{c}")
+            }
+            '\n' => "\\n".to_string(),
+            '\r' => "\\r".to_string(),
+            '\t' => "\\t".to_string(),
+            // Line separators would end the regex literal in older engines.
+            _ if c.is_control() || c == '\u{2028}' || c == '\u{2029}' => {
+                format!("\\u{:04X}", c as u32)
+            }
+            _ => c.to_string(),
+        }
+    }
+
+    fn output_set_item(&self, item: &SetItem) -> Result<String, OutputError> {
+        match item {
+            SetItem::Char(c) => self.output_set_char(*c),
+            SetItem::Range(from, to) => self.output_range(*from, *to),
+            SetItem::CharClass(class) => self.output_char_class(class),
+            SetItem::Symbol(_) | SetItem::SymbolRange(_, _) => {
+                Err(OutputError::FeatureNotSupported("unresolved definitions"))
+            }
+        }
+    }
+
+    fn output_range(&self, from: char, to: char) -> Result<String, OutputError> {
+        Ok(format!(
+            "{}-{}",
+            self.output_set_char(from)?,
+            self.output_set_char(to)?
+        ))
+    }
+
+    fn output_set_char(&self, c: char) -> Result<String, OutputError> {
+        match c {
+            // The `v` flag reserves more punctuation in sets.
+            _ if self.unicode_sets && "()[]{}/-\\|&!#$%*+,.:;<=>?@^`~".contains(c) => {
+                Ok(format!("\\{c}"))
+            }
+            ']' | '\\' | '^' | '-' => Ok(format!("\\{c}")),
+            '\n' => Ok("\\n".to_string()),
+            '\r' => Ok("\\r".to_string()),
+            '\t' => Ok("\\t".to_string()),
+            _ if c.is_ascii() && !c.is_ascii_control() => Ok(c.to_string()),
+            _ if (c as u32) <= 0xFFFF => Ok(format!("\\u{:04X}", c as u32)),
+            _ if self.is_unicode() => Ok(format!("\\u{{{:X}}}", c as u32)),
+            _ => Err(OutputError::FeatureNotSupported(
+                "astral code points without the u flag",
+            )),
         }
     }
 
@@ -166,6 +292,15 @@ impl JavascriptOutput {
             CharClass::LowerCase => Ok("a-z".to_string()),
             CharClass::UpperCase => Ok("A-Z".to_string()),
             CharClass::Word => Ok("\\w".to_string()),
+            CharClass::NotWhitespace => Ok("\\S".to_string()),
+            CharClass::NotDigit => Ok("\\D".to_string()),
+            CharClass::NotWord => Ok("\\W".to_string()),
+            CharClass::Category(_) | CharClass::Script(_) if !self.is_unicode() => Err(
+                OutputError::FeatureNotSupported("unicode properties without the u flag"),
+            ),
+            CharClass::Category(category) => Ok(format!("\\p{{{category}}}")),
+            CharClass::Script(script) => Ok(format!("\\p{{Script={script}}}")),
+            CharClass::Block(from, to) => self.output_range(*from, *to),
         }
     }
 
@@ -173,8 +308,169 @@ impl JavascriptOutput {
         match assertion {
             Assertion::LineStart => Ok("^".to_string()),
             Assertion::LineEnd => Ok("$".to_string()),
+            // Without the `m` flag, `^` and `$` only match at the
+            // start and end of the input.
+            Assertion::StringStart if !self.multiline => Ok("^".to_string()),
+            Assertion::StringStart => Ok("(?<![\\s\\S])".to_string()),
+            Assertion::StringEnd if !self.multiline => Ok("$".to_string()),
+            Assertion::StringEnd => Ok("(?![\\s\\S])".to_string()),
+            Assertion::StringEndBeforeNewline => Ok("(?=\\n?(?![\\s\\S]))".to_string()),
             Assertion::WordBoundary => Ok("\\b".to_string()),
             Assertion::NotWordBoundary => Ok("\\B".to_string()),
+            Assertion::WordStart => Ok("\\b(?=\\w)".to_string()),
+            Assertion::WordEnd => Ok("\\b(?<=\\w)".to_string()),
+            Assertion::SymbolStart => Ok("(?<![\\w$])(?=[\\w$])".to_string()),
+            Assertion::SymbolEnd => Ok("(?<=[\\w$])(?![\\w$])".to_string()),
+            Assertion::LookAhead(exprs) => Ok(format!("(?={})", self.output_seq(exprs)?)),
+            Assertion::NegativeLookAhead(exprs) => Ok(format!("(?!{})", self.output_seq(exprs)?)),
+            Assertion::LookBehind(exprs) => Ok(format!("(?<={})", self.output_seq(exprs)?)),
+            Assertion::NegativeLookBehind(exprs) => Ok(format!("(?<!{})", self.output_seq(exprs)?)),
+        }
+    }
+}
+
+/// The characters in a class. Unlike in other flavors, `\s` also
+/// matches Unicode spaces.
+fn class_set(class: &CharClass) -> Option<CharSet> {
+    let whitespace = || {
+        CharSet::new(vec![
+            (0x09, 0x0D),
+            (0x20, 0x20),
+            (0xA0, 0xA0),
+            (0x1680, 0x1680),
+            (0x2000, 0x200A),
+            (0x2028, 0x2029),
+            (0x202F, 0x202F),
+            (0x205F, 0x205F),
+            (0x3000, 0x3000),
+            (0xFEFF, 0xFEFF),
+        ])
+    };
+    match class {
+        CharClass::Whitespace => Some(whitespace()),
+        CharClass::NotWhitespace => Some(whitespace().complement()),
+        _ => ascii_class_set(class),
+    }
+}
+
+/// Rewrites an expression to match regardless of case, by replacing
+/// characters with sets of their case variants.
+fn fold_case(expr: &mut Expr) -> Result<(), OutputError> {
+    match expr {
+        Expr::Atom(Atom::Char(c)) => {
+            let variants = case_variants(*c);
+            if variants.len() > 1 {
+                *expr = Expr::Any(variants.into_iter().map(SetItem::Char).collect());
+            }
+        }
+        Expr::Atom(Atom::String(s)) => {
+            let mut exprs: Vec<Expr> = s.chars().map(|c| Expr::Atom(Atom::Char(c))).collect();
+            for e in exprs.iter_mut() {
+                fold_case(e)?;
+            }
+            *expr = match exprs.len() {
+                1 => exprs.remove(0),
+                _ => Expr::Seq(exprs),
+            };
+        }
+        Expr::Atom(Atom::CharClass(class)) => fold_case_class(class),
+        Expr::Any(items) | Expr::Not(items) => {
+            let mut folded = vec![];
+            for item in items.iter() {
+                match item {
+                    SetItem::Char(c) => {
+                        folded.extend(case_variants(*c).into_iter().map(SetItem::Char));
+                    }
+                    SetItem::Range(from, to) => {
+                        folded.push(SetItem::Range(*from, *to));
+                        // Only ASCII ranges are folded.
+                        for (lower, upper) in [('a', 'z'), ('A', 'Z')] {
+                            let (from, to) = ((*from).max(lower), (*to).min(upper));
+                            if from <= to {
+                                folded.push(SetItem::Range(swap_case(from), swap_case(to)));
+                            }
+                        }
+                    }
+                    SetItem::CharClass(class) => {
+                        let mut class = class.clone();
+                        fold_case_class(&mut class);
+                        folded.push(SetItem::CharClass(class));
+                    }
+                    SetItem::Symbol(_) | SetItem::SymbolRange(_, _) => folded.push(item.clone()),
+                }
+            }
+            *items = folded;
+        }
+        Expr::BackRef(_) => {
+            return Err(OutputError::FeatureNotSupported(
+                "case-insensitive backrefs",
+            ))
+        }
+        Expr::Raw(_) => {
+            return Err(OutputError::FeatureNotSupported(
+                "case-insensitive raw regexps",
+            ))
+        }
+        Expr::Scoped(Flag::CaseSensitive, _) => {}
+        _ => {
+            for e in expr.children_mut() {
+                fold_case(e)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn fold_case_class(class: &mut CharClass) {
+    match class {
+        CharClass::LowerCase | CharClass::UpperCase => *class = CharClass::Alpha,
+        CharClass::Category("Ll" | "Lu" | "Lt") => *class = CharClass::Category("LC"),
+        _ => {}
+    }
+}
+
+/// The character and its single-character lower and upper case
+/// variants, if any.
+fn case_variants(c: char) -> Vec<char> {
+    let mut variants = vec![c];
+    let lower: Vec<char> = c.to_lowercase().collect();
+    let upper: Vec<char> = c.to_uppercase().collect();
+    for variant in [lower, upper] {
+        if let [v] = variant[..] {
+            if !variants.contains(&v) {
+                variants.push(v);
+            }
+        }
+    }
+    variants
+}
+
+fn swap_case(c: char) -> char {
+    if c.is_ascii_lowercase() {
+        c.to_ascii_uppercase()
+    } else {
+        c.to_ascii_lowercase()
+    }
+}
+
+/// Rewrites line anchors to match at every line, as if the `m` flag
+/// was set.
+fn emulate_multiline(expr: &mut Expr) {
+    match expr {
+        Expr::Assertion(Assertion::LineStart) => {
+            *expr = Expr::Assertion(Assertion::NegativeLookBehind(vec![Expr::Not(vec![
+                SetItem::Char('\n'),
+            ])]))
+        }
+        Expr::Assertion(Assertion::LineEnd) => {
+            *expr = Expr::Assertion(Assertion::NegativeLookAhead(vec![Expr::Not(vec![
+                SetItem::Char('\n'),
+            ])]))
+        }
+        _ => {
+            for e in expr.children_mut() {
+                emulate_multiline(e);
+            }
         }
     }
 }
@@ -1,17 +1,208 @@
-use crate::expr::Expr;
+use crate::expr::{Expr, GroupRef};
 
 pub mod debug;
 pub mod javascript;
 pub mod pcre;
 pub mod pcre2;
+pub mod posix_bre;
+pub mod posix_ere;
+pub mod python;
+pub mod re2;
+pub mod rust_regex;
+mod set;
 
 pub trait Output {
     fn output(&self, expr: &Expr) -> Result<String, OutputError>;
 }
 
+/// Checks that the groups referenced by backrefs, conditionals and
+/// calls exist somewhere in the expression.
+pub(crate) fn check_group_refs(expr: &Expr) -> Result<(), OutputError> {
+    let mut count = 0;
+    let mut names = vec![];
+    collect_groups(expr, &mut count, &mut names);
+    check_group_refs_in(expr, count, &names)
+}
+
+fn collect_groups<'a>(expr: &'a Expr, count: &mut u32, names: &mut Vec<&'a str>) {
+    match expr {
+        Expr::Group(_) | Expr::GroupN(_, _) => *count += 1,
+        Expr::GroupNamed(name, _) => {
+            *count += 1;
+            names.push(name);
+        }
+        _ => {}
+    }
+    for child in expr.children() {
+        collect_groups(child, count, names);
+    }
+}
+
+fn check_group_refs_in(expr: &Expr, count: u32, names: &[&str]) -> Result<(), OutputError> {
+    match expr {
+        Expr::BackRef(GroupRef::Number(n))
+        | Expr::IfGroup(GroupRef::Number(n), _)
+        | Expr::Call(GroupRef::Number(n))
+            if *n == 0 || *n > count =>
+        {
+            return Err(OutputError::UnknownGroup(n.to_string()));
+        }
+        Expr::BackRef(GroupRef::Name(name))
+        | Expr::IfGroup(GroupRef::Name(name), _)
+        | Expr::Call(GroupRef::Name(name))
+            if !names.contains(&name.as_str()) =>
+        {
+            return Err(OutputError::UnknownGroup(name.clone()));
+        }
+        _ => {}
+    }
+    for child in expr.children() {
+        check_group_refs_in(child, count, names)?;
+    }
+    Ok(())
+}
+
+/// Fails if the expression recurses, for output formats without
+/// recursion. Checking up front reports that, rather than whatever
+/// else the expansion of a form like `balanced` uses.
+pub(crate) fn check_no_recursion(expr: &Expr) -> Result<(), OutputError> {
+    if let Expr::Recurse | Expr::Call(_) = expr {
+        return Err(OutputError::FeatureNotSupported("recursion"));
+    }
+    for child in expr.children() {
+        check_no_recursion(child)?;
+    }
+    Ok(())
+}
+
+/// Finds the raw regex text for the output format, preferring a
+/// variant for that format over one for any format.
+pub(crate) fn raw_variant<'a>(
+    variants: &'a [(Option<String>, String)],
+    format: &'static str,
+) -> Result<&'a str, OutputError> {
+    let (_, text) = variants
+        .iter()
+        .find(|(name, _)| name.as_deref() == Some(format))
+        .or_else(|| variants.iter().find(|(name, _)| name.is_none()))
+        .ok_or(OutputError::NoRawVariant(format))?;
+    let balanced = match format {
+        "posix-bre" => bre_group_count(text).is_some(),
+        _ => is_balanced(text),
+    };
+    if !balanced {
+        return Err(OutputError::UnbalancedRaw(text.clone()));
+    }
+    Ok(text)
+}
+
+/// Whether every group opened in the regex text is also closed in it,
+/// ignoring escaped characters, `\Q…\E` quotes and characters in
+/// brackets, including POSIX classes like `[:alpha:]` in them.
+fn is_balanced(text: &str) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    let mut depth = 0u32;
+    let mut i = 0;
+    let mut in_set = false;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 == chars.len() => return false,
+            '\\' if chars[i + 1] == 'Q' => {
+                // An unterminated quote runs to the end.
+                match find(&chars[i + 2..], &['\\', 'E']) {
+                    Some(end) => i += end + 3,
+                    None => break,
+                }
+            }
+            '\\' => i += 1,
+            '[' if !in_set => {
+                in_set = true;
+                if chars.get(i + 1) == Some(&'^') {
+                    i += 1;
+                }
+                // A leading `]` is a literal.
+                if chars.get(i + 1) == Some(&']') {
+                    i += 1;
+                }
+            }
+            '[' if matches!(chars.get(i + 1), Some(':' | '.' | '=')) => {
+                let end = [chars[i + 1], ']'];
+                match find(&chars[i + 2..], &end) {
+                    Some(end) => i += end + 3,
+                    None => return false,
+                }
+            }
+            ']' if in_set => in_set = false,
+            _ if in_set => {}
+            '(' => depth += 1,
+            ')' if depth == 0 => return false,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    depth == 0 && !in_set
+}
+
+/// The number of groups in POSIX BRE text, or `None` if they are not
+/// balanced. Groups are `\(…\)`, while plain parentheses are literal,
+/// and so are backslashes in brackets.
+pub(crate) fn bre_group_count(text: &str) -> Option<u32> {
+    let chars: Vec<char> = text.chars().collect();
+    let (mut depth, mut count) = (0u32, 0u32);
+    let mut i = 0;
+    let mut in_set = false;
+    while i < chars.len() {
+        match chars[i] {
+            '[' if !in_set => {
+                in_set = true;
+                if chars.get(i + 1) == Some(&'^') {
+                    i += 1;
+                }
+                // A leading `]` is a literal.
+                if chars.get(i + 1) == Some(&']') {
+                    i += 1;
+                }
+            }
+            '[' if in_set && matches!(chars.get(i + 1), Some(':' | '.' | '=')) => {
+                let end = [chars[i + 1], ']'];
+                i += find(&chars[i + 2..], &end)? + 3;
+            }
+            ']' if in_set => in_set = false,
+            _ if in_set => {}
+            '\\' => {
+                match chars.get(i + 1)? {
+                    '(' => {
+                        depth += 1;
+                        count += 1;
+                    }
+                    ')' => depth = depth.checked_sub(1)?,
+                    _ => {}
+                }
+                i += 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    (depth == 0 && !in_set).then_some(count)
+}
+
+/// The index of the first occurrence of `needle` in `chars`.
+fn find(chars: &[char], needle: &[char]) -> Option<usize> {
+    chars
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
 #[derive(Debug)]
 pub enum OutputError {
     FeatureNotSupported(&'static str),
+    NoRawVariant(&'static str),
+    UnbalancedRaw(String),
+    UnknownGroup(String),
+    NotACharSet,
+    ReversedRepetition(u32, u32),
 }
 
 impl std::error::Error for OutputError {}
@@ -22,6 +213,20 @@ impl std::fmt::Display for OutputError {
             Self::FeatureNotSupported(feat) => {
                 write!(f, "feature is not supported by output format: {feat}")
             }
+            Self::NoRawVariant(format) => {
+                write!(f, "no raw regexp given for output format: {format}")
+            }
+            Self::UnbalancedRaw(text) => write!(f, "raw regexp has unbalanced groups: {text}"),
+            Self::NotACharSet => write!(f, "set operation on something other than a character set"),
+            Self::UnknownGroup(group) => {
+                write!(f, "reference to a group that does not exist: {group}")
+            }
+            Self::ReversedRepetition(min, max) => {
+                write!(
+                    f,
+                    "repetition minimum is above its maximum: {{{min},{max}}}"
+                )
+            }
         }
     }
 }
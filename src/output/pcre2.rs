@@ -1,12 +1,18 @@
-use crate::expr::{Assertion, Atom, CharClass, Expr};
-use crate::output::{Output, OutputError};
+use crate::expr::{seq_width, Assertion, Atom, CharClass, Expr, Flag, GroupRef, SetItem};
+use crate::output::set::{
+    ascii_class_set, case_insensitive_set_operations, eval_set, set_operation_lookarounds,
+};
+use crate::output::{check_group_refs, raw_variant, Output, OutputError};
 
 #[derive(Copy, Clone, Default)]
 pub struct PCRE2Output {}
 
 impl Output for PCRE2Output {
     fn output(&self, expr: &Expr) -> Result<String, OutputError> {
-        self.output_expr(expr)
+        check_group_refs(expr)?;
+        let mut expr = expr.clone();
+        case_insensitive_set_operations(&mut expr, false);
+        self.output_expr(&expr)
     }
 }
 
@@ -14,152 +20,203 @@ impl PCRE2Output {
     fn output_expr(&self, expr: &Expr) -> Result<String, OutputError> {
         match expr {
             Expr::Atom(c) => self.output_atom(c),
-            Expr::Seq(exprs) => {
-                let mut s = String::new();
-                for e in exprs {
-                    // NB In a sequence, we need to avoid mushing
-                    // together several tokens that will then get
-                    // affected by a postfix modifier.
-                    match e {
-                        Expr::Atom(_) => s.push_str(&self.output_expr(e)?),
-                        Expr::Assertion(_) => s.push_str(&self.output_expr(e)?),
-                        _ => s.push_str(&format!("(?:{})", self.output_expr(e)?)),
-                    }
-                }
-                Ok(s)
-            }
+            Expr::Seq(exprs) => self.output_seq(exprs),
             Expr::Or(exprs) if exprs.len() == 1 => self.output_expr(exprs.first().expect("")),
             Expr::Or(exprs) => {
-                let mut s = format!("{}|", self.output_expr(exprs.first().expect(""))?);
+                let mut s = self.output_expr(exprs.first().expect(""))?;
                 for e in exprs[1..].iter() {
+                    s.push('|');
                     s.push_str(&self.output_expr(e)?);
                 }
                 Ok(s)
             }
-            Expr::ZeroOrOne(exprs) => {
-                let mut s = String::from("(?:");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
-                }
-                s.push('?');
-                Ok(s)
+            Expr::ZeroOrOne(exprs) => self.output_quantified(exprs, "?"),
+            Expr::ZeroOrOneReluctant(exprs) => self.output_quantified(exprs, "??"),
+            Expr::ZeroOrOnePossessive(exprs) => self.output_quantified(exprs, "?+"),
+            Expr::ZeroOrMore(exprs) => self.output_quantified(exprs, "*"),
+            Expr::ZeroOrMoreReluctant(exprs) => self.output_quantified(exprs, "*?"),
+            Expr::ZeroOrMorePossessive(exprs) => self.output_quantified(exprs, "*+"),
+            Expr::OneOrMore(exprs) => self.output_quantified(exprs, "+"),
+            Expr::OneOrMoreReluctant(exprs) => self.output_quantified(exprs, "+?"),
+            Expr::OneOrMorePossessive(exprs) => self.output_quantified(exprs, "++"),
+            Expr::Exactly(n, exprs) => self.output_counted(exprs, &format!("{{{n}}}")),
+            Expr::ExactlyPossessive(n, exprs) => self.output_counted(exprs, &format!("{{{n}}}+")),
+            Expr::AtLeast(n, exprs) => self.output_counted(exprs, &format!("{{{n},}}")),
+            Expr::AtLeastReluctant(n, exprs) => self.output_counted(exprs, &format!("{{{n},}}?")),
+            Expr::AtLeastPossessive(n, exprs) => self.output_counted(exprs, &format!("{{{n},}}+")),
+            Expr::Between(n, m, exprs) => self.output_counted(exprs, &format!("{{{n},{m}}}")),
+            Expr::BetweenReluctant(n, m, exprs) => {
+                self.output_counted(exprs, &format!("{{{n},{m}}}?"))
             }
-            Expr::ZeroOrMore(exprs) if exprs.len() == 1 => {
-                Ok(format!("{}*", self.output_expr(exprs.first().unwrap())?))
-            }
-            Expr::ZeroOrMore(exprs) => {
-                let mut s = String::from("(?:");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
-                }
-                s.push_str(")*");
-                Ok(s)
-            }
-            Expr::ZeroOrMoreReluctant(exprs) if exprs.len() == 1 => {
-                Ok(format!("{}*?", self.output_expr(exprs.first().unwrap())?))
-            }
-            Expr::ZeroOrMoreReluctant(exprs) => {
-                let mut s = String::from("(?:");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
-                }
-                s.push_str(")*?");
-                Ok(s)
-            }
-            Expr::OneOrMore(exprs) if exprs.len() == 1 => {
-                Ok(format!("{}+", self.output_expr(exprs.first().unwrap())?))
-            }
-            Expr::OneOrMore(exprs) => {
-                let mut s = String::from("(?:");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
-                }
-                s.push_str(")+");
-                Ok(s)
-            }
-            Expr::OneOrMoreReluctant(exprs) if exprs.len() == 1 => {
-                Ok(format!("{}+?", self.output_expr(exprs.first().unwrap())?))
-            }
-            Expr::OneOrMoreReluctant(exprs) => {
-                let mut s = String::from("(?:");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
-                }
-                s.push_str(")+?");
-                Ok(s)
-            }
-            Expr::Exactly(n, exprs) => {
-                let mut s = String::from("(?:");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
-                }
-                s.push_str(&format!("){{{n}}}"));
-                Ok(s)
-            }
-            Expr::AtLeast(n, exprs) => {
-                let mut s = String::from("(?:");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
-                }
-                s.push_str(&format!("){{{n},}}"));
-                Ok(s)
-            }
-            Expr::Between(n, m, exprs) => {
-                let mut s = String::from("(?:");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
-                }
-                s.push_str(&format!("){{{n},{m}}}"));
-                Ok(s)
+            Expr::BetweenPossessive(n, m, exprs) => {
+                self.output_counted(exprs, &format!("{{{n},{m}}}+"))
             }
+            Expr::Atomic(exprs) => Ok(format!("(?>{})", self.output_seq(exprs)?)),
             Expr::Assertion(assertion) => self.output_assertion(assertion),
-            Expr::Any(atoms) => {
+            Expr::Intersection(_) | Expr::Difference(_) => self.output_set_operation(expr),
+            Expr::Any(items) => {
                 let mut s = String::from("[");
-                for a in atoms {
-                    if let Atom::CharClass(c) = a {
-                        s.push_str(&self.output_char_class(c)?);
-                    } else {
-                        s.push_str(&self.output_atom(a)?);
-                    }
+                for item in items {
+                    s.push_str(&self.output_set_item(item)?);
                 }
                 s.push(']');
                 Ok(s)
             }
-            Expr::Not(Atom::CharClass(class)) => {
-                Ok(format!("[^{}]", self.output_char_class(class)?))
-            }
-            Expr::Not(atom) => Ok(format!("[^{}]", self.output_atom(atom)?)),
-            Expr::Group(exprs) => {
-                let mut s = String::from("(");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
+            Expr::Not(items) => {
+                let mut s = String::from("[^");
+                for item in items {
+                    s.push_str(&self.output_set_item(item)?);
                 }
-                s.push(')');
+                s.push(']');
                 Ok(s)
             }
-            Expr::GroupN(n, exprs) => {
-                let mut s = format!("(?<n{n}>");
-                for e in exprs {
-                    s.push_str(&self.output_expr(e)?);
+            Expr::Group(exprs) => Ok(format!("({})", self.output_seq(exprs)?)),
+            Expr::GroupN(n, exprs) => Ok(format!("(?<n{n}>{})", self.output_seq(exprs)?)),
+            Expr::GroupNamed(name, exprs) => Ok(format!("(?<{name}>{})", self.output_seq(exprs)?)),
+            Expr::BackRef(GroupRef::Number(n)) => Ok(format!("\\{n}")),
+            Expr::BackRef(GroupRef::Name(name)) => Ok(format!("\\k<{name}>")),
+            Expr::BackRef(GroupRef::Relative(n)) => Ok(format!("\\g{{{n:+}}}")),
+            Expr::Recurse => Ok("(?R)".to_string()),
+            Expr::Call(GroupRef::Number(n)) => Ok(format!("(?{n})")),
+            Expr::Call(GroupRef::Name(name)) => Ok(format!("(?&{name})")),
+            Expr::Call(GroupRef::Relative(n)) => Ok(format!("(?{n:+})")),
+            Expr::IfGroup(group, exprs) => {
+                let condition = match group {
+                    GroupRef::Number(n) => n.to_string(),
+                    GroupRef::Name(name) => format!("<{name}>"),
+                    GroupRef::Relative(n) => format!("{n:+}"),
+                };
+                let mut s = format!("(?({condition})");
+                for (idx, e) in exprs.iter().enumerate() {
+                    if idx > 0 {
+                        s.push('|');
+                    }
+                    // An alternation would add more branches.
+                    match e {
+                        Expr::Or(_) => s.push_str(&format!("(?:{})", self.output_expr(e)?)),
+                        _ => s.push_str(&self.output_expr(e)?),
+                    }
                 }
                 s.push(')');
                 Ok(s)
             }
-            Expr::BackRef(n) => {
-                if n.chars().all(|c| c.is_ascii_digit()) {
-                    Ok(format!("${n}"))
-                } else {
-                    Ok(format!("${{{n}}}"))
+            Expr::Raw(variants) => Ok(raw_variant(variants, "pcre2")?.to_string()),
+            Expr::Reference(_) | Expr::Symbol(_) | Expr::Let(_, _) | Expr::MacroCall(_, _, _) => {
+                Err(OutputError::FeatureNotSupported("unresolved definitions"))
+            }
+            Expr::Scoped(flag, exprs) => {
+                let flag = match flag {
+                    Flag::CaseInsensitive => "i",
+                    Flag::CaseSensitive => "-i",
+                    Flag::Multiline => "m",
+                    Flag::DotAll => "s",
+                };
+                Ok(format!("(?{flag}:{})", self.output_seq(exprs)?))
+            }
+        }
+    }
+
+    fn output_seq(&self, exprs: &[Expr]) -> Result<String, OutputError> {
+        if let [e] = exprs {
+            return self.output_expr(e);
+        }
+        self.output_seq_items(exprs)
+    }
+
+    fn output_seq_items(&self, exprs: &[Expr]) -> Result<String, OutputError> {
+        let mut s = String::new();
+        for e in exprs {
+            // NB In a sequence, we need to avoid mushing
+            // together several tokens that will then get
+            // affected by a postfix modifier.
+            match e {
+                Expr::Atom(_) => s.push_str(&self.output_expr(e)?),
+                Expr::Assertion(_) => s.push_str(&self.output_expr(e)?),
+                Expr::Any(_) | Expr::Not(_) | Expr::Intersection(_) | Expr::Difference(_) => {
+                    s.push_str(&self.output_expr(e)?)
                 }
+                Expr::Seq(exprs) => s.push_str(&self.output_seq_items(exprs)?),
+                _ => s.push_str(&format!("(?:{})", self.output_expr(e)?)),
             }
         }
+        Ok(s)
+    }
+
+    fn output_set_operation(&self, expr: &Expr) -> Result<String, OutputError> {
+        match eval_set(expr, &ascii_class_set)? {
+            Some(set) => self.output_expr(&set.to_expr()),
+            None => self.output_seq_items(&set_operation_lookarounds(expr)),
+        }
+    }
+
+    fn output_quantified(&self, exprs: &[Expr], quantifier: &str) -> Result<String, OutputError> {
+        match exprs {
+            // Single characters can take a quantifier directly, but
+            // anything longer needs to be grouped.
+            [Expr::Atom(Atom::String(s))] if s.chars().count() != 1 => {
+                Ok(format!("(?:{}){quantifier}", self.output_seq(exprs)?))
+            }
+            [Expr::Atom(atom)] => Ok(format!("{}{quantifier}", self.output_atom(atom)?)),
+            _ => Ok(format!("(?:{}){quantifier}", self.output_seq(exprs)?)),
+        }
+    }
+
+    fn output_counted(&self, exprs: &[Expr], quantifier: &str) -> Result<String, OutputError> {
+        Ok(format!("(?:{}){quantifier}", self.output_seq(exprs)?))
     }
 
     fn output_atom(&self, atom: &Atom) -> Result<String, OutputError> {
         match atom {
-            Atom::Char(c) => Ok(format!("{c}")),
-            Atom::String(s) => Ok(s.clone()),
+            Atom::Char(c) => Ok(self.output_char(*c)),
+            Atom::String(s) => Ok(s.chars().map(|c| self.output_char(c)).collect()),
             Atom::CharClass(class) => Ok(format!("[{}]", self.output_char_class(class)?)),
+            Atom::NotNewline => Ok("\\N".to_string()),
+            Atom::AnyChar => Ok("(?s:.)".to_string()),
+            Atom::Grapheme => Ok("\\X".to_string()),
+            Atom::NewlineSequence => Ok("\\R".to_string()),
+        }
+    }
+
+    fn output_char(&self, c: char) -> String {
+        match c {
+            '\\' | '^' | '$' | '.' | '|' | '?' | '*' | '+' | '(' | ')' | '[' | ']' | '{' | '}' => {
+                format!("\\{c}")
+            }
+            '\n' => "\\n".to_string(),
+            '\r' => "\\r".to_string(),
+            '\t' => "\\t".to_string(),
+            _ if c.is_control() => format!("\\x{{{:X}}}", c as u32),
+            _ => c.to_string(),
+        }
+    }
+
+    fn output_set_item(&self, item: &SetItem) -> Result<String, OutputError> {
+        match item {
+            SetItem::Char(c) => self.output_set_char(*c),
+            SetItem::Range(from, to) => self.output_range(*from, *to),
+            SetItem::CharClass(class) => self.output_char_class(class),
+            SetItem::Symbol(_) | SetItem::SymbolRange(_, _) => {
+                Err(OutputError::FeatureNotSupported("unresolved definitions"))
+            }
+        }
+    }
+
+    fn output_range(&self, from: char, to: char) -> Result<String, OutputError> {
+        Ok(format!(
+            "{}-{}",
+            self.output_set_char(from)?,
+            self.output_set_char(to)?
+        ))
+    }
+
+    fn output_set_char(&self, c: char) -> Result<String, OutputError> {
+        match c {
+            ']' | '\\' | '^' | '-' => Ok(format!("\\{c}")),
+            '\n' => Ok("\\n".to_string()),
+            '\r' => Ok("\\r".to_string()),
+            '\t' => Ok("\\t".to_string()),
+            _ if c.is_ascii() && !c.is_ascii_control() => Ok(c.to_string()),
+            _ => Ok(format!("\\x{{{:X}}}", c as u32)),
         }
     }
 
@@ -173,6 +230,12 @@ impl PCRE2Output {
             CharClass::LowerCase => Ok("a-z".to_string()),
             CharClass::UpperCase => Ok("A-Z".to_string()),
             CharClass::Word => Ok("\\w".to_string()),
+            CharClass::NotWhitespace => Ok("\\S".to_string()),
+            CharClass::NotDigit => Ok("\\D".to_string()),
+            CharClass::NotWord => Ok("\\W".to_string()),
+            CharClass::Category(category) => Ok(format!("\\p{{{category}}}")),
+            CharClass::Script(script) => Ok(format!("\\p{{{script}}}")),
+            CharClass::Block(from, to) => self.output_range(*from, *to),
         }
     }
 
@@ -180,8 +243,30 @@ impl PCRE2Output {
         match assertion {
             Assertion::LineStart => Ok("^".to_string()),
             Assertion::LineEnd => Ok("$".to_string()),
+            Assertion::StringStart => Ok("\\A".to_string()),
+            Assertion::StringEnd => Ok("\\z".to_string()),
+            Assertion::StringEndBeforeNewline => Ok("\\Z".to_string()),
             Assertion::WordBoundary => Ok("\\b".to_string()),
             Assertion::NotWordBoundary => Ok("\\B".to_string()),
+            Assertion::WordStart => Ok("[[:<:]]".to_string()),
+            Assertion::WordEnd => Ok("[[:>:]]".to_string()),
+            Assertion::SymbolStart => Ok("(?<![\\w$])(?=[\\w$])".to_string()),
+            Assertion::SymbolEnd => Ok("(?<=[\\w$])(?![\\w$])".to_string()),
+            Assertion::LookAhead(exprs) => Ok(format!("(?={})", self.output_seq(exprs)?)),
+            Assertion::NegativeLookAhead(exprs) => Ok(format!("(?!{})", self.output_seq(exprs)?)),
+            Assertion::LookBehind(exprs) => Ok(format!("(?<={})", self.output_look_behind(exprs)?)),
+            Assertion::NegativeLookBehind(exprs) => {
+                Ok(format!("(?<!{})", self.output_look_behind(exprs)?))
+            }
+        }
+    }
+
+    fn output_look_behind(self, exprs: &[Expr]) -> Result<String, OutputError> {
+        // PCRE2 allows variable-length lookbehinds, as long as they
+        // are bounded to at most 255 characters (since 10.43).
+        match seq_width(exprs) {
+            (_, Some(max)) if max <= 255 => self.output_seq(exprs),
+            _ => Err(OutputError::FeatureNotSupported("unbounded lookbehind")),
         }
     }
 }
@@ -0,0 +1,433 @@
+use crate::expr::{Assertion, Atom, CharClass, Expr, GroupRef, SetItem};
+use crate::output::set::{ascii_class_set, eval_set};
+use crate::output::{bre_group_count, check_no_recursion, raw_variant, Output, OutputError};
+
+/// POSIX basic regular expressions, as used by `sed` and `grep`.
+/// There are no escape sequences, so control characters are output
+/// as they are, except for newlines, which grep splits its pattern on.
+#[derive(Copy, Clone, Default)]
+pub struct PosixBreOutput {
+    /// Whether to use GNU extensions, such as `\+`, `\?`, `\|` and
+    /// word boundaries.
+    pub gnu: bool,
+}
+
+/// The groups output so far. Every group captures, including those
+/// only needed for grouping, so backrefs are renumbered.
+#[derive(Default)]
+struct Groups {
+    count: u32,
+    /// The number in the output of each group in the expression.
+    numbers: Vec<u32>,
+    names: Vec<(String, u32)>,
+}
+
+impl Groups {
+    fn open(&mut self) -> u32 {
+        self.count += 1;
+        self.count
+    }
+}
+
+impl Output for PosixBreOutput {
+    fn output(&self, expr: &Expr) -> Result<String, OutputError> {
+        check_no_recursion(expr)?;
+        self.output_expr(expr, &mut Groups::default())
+    }
+}
+
+impl PosixBreOutput {
+    fn output_expr(&self, expr: &Expr, groups: &mut Groups) -> Result<String, OutputError> {
+        match expr {
+            Expr::Atom(c) => self.output_atom(c),
+            Expr::Seq(exprs) => self.output_seq(exprs, groups),
+            Expr::Or(exprs) if exprs.len() == 1 => {
+                self.output_expr(exprs.first().expect(""), groups)
+            }
+            Expr::Or(_) if !self.gnu => Err(OutputError::FeatureNotSupported("alternation")),
+            Expr::Or(exprs) => {
+                let mut s = self.output_expr(exprs.first().expect(""), groups)?;
+                for e in exprs[1..].iter() {
+                    s.push_str("\\|");
+                    s.push_str(&self.output_expr(e, groups)?);
+                }
+                Ok(s)
+            }
+            Expr::ZeroOrOne(exprs) if self.gnu => self.output_quantified(exprs, "\\?", groups),
+            Expr::ZeroOrOne(exprs) => self.output_quantified(exprs, "\\{0,1\\}", groups),
+            Expr::ZeroOrMore(exprs) => self.output_quantified(exprs, "*", groups),
+            Expr::OneOrMore(exprs) if self.gnu => self.output_quantified(exprs, "\\+", groups),
+            // Repeating groups would renumber them, and repeating
+            // anchors would leave a copy in the middle of the pattern.
+            Expr::OneOrMore(exprs) if exprs.iter().any(|e| has_groups(e) || has_anchors(e)) => {
+                self.output_quantified(exprs, "\\{1,\\}", groups)
+            }
+            Expr::OneOrMore(exprs) => Ok(format!(
+                "{}{}",
+                self.output_seq_items(exprs, groups)?,
+                self.output_quantified(exprs, "*", groups)?
+            )),
+            Expr::Exactly(n, exprs) => {
+                self.output_quantified(exprs, &format!("\\{{{n}\\}}"), groups)
+            }
+            Expr::AtLeast(n, exprs) => {
+                self.output_quantified(exprs, &format!("\\{{{n},\\}}"), groups)
+            }
+            Expr::Between(n, m, exprs) => {
+                self.output_quantified(exprs, &format!("\\{{{n},{m}\\}}"), groups)
+            }
+            Expr::ZeroOrOneReluctant(_)
+            | Expr::ZeroOrMoreReluctant(_)
+            | Expr::OneOrMoreReluctant(_)
+            | Expr::AtLeastReluctant(_, _)
+            | Expr::BetweenReluctant(_, _, _) => {
+                Err(OutputError::FeatureNotSupported("reluctant quantifiers"))
+            }
+            Expr::ZeroOrOnePossessive(_)
+            | Expr::ZeroOrMorePossessive(_)
+            | Expr::OneOrMorePossessive(_)
+            | Expr::ExactlyPossessive(_, _)
+            | Expr::AtLeastPossessive(_, _)
+            | Expr::BetweenPossessive(_, _, _) => {
+                Err(OutputError::FeatureNotSupported("possessive quantifiers"))
+            }
+            Expr::Atomic(_) => Err(OutputError::FeatureNotSupported("atomic groups")),
+            Expr::Assertion(assertion) => self.output_assertion(assertion),
+            Expr::Intersection(_) | Expr::Difference(_) => self.output_set_operation(expr, groups),
+            Expr::Any(items) => self.output_bracket(items, false),
+            Expr::Not(items) => self.output_bracket(items, true),
+            Expr::Group(exprs) => {
+                let n = groups.open();
+                groups.numbers.push(n);
+                Ok(format!("\\({}\\)", self.output_seq(exprs, groups)?))
+            }
+            // Group names only exist to be referred to by backrefs.
+            Expr::GroupNamed(name, exprs) => {
+                let n = groups.open();
+                groups.numbers.push(n);
+                groups.names.push((name.clone(), n));
+                Ok(format!("\\({}\\)", self.output_seq(exprs, groups)?))
+            }
+            // Every group captures, so group numbers cannot be chosen.
+            Expr::GroupN(_, _) => Err(OutputError::FeatureNotSupported("numbered groups")),
+            Expr::BackRef(group) => {
+                let (n, name) = match group {
+                    GroupRef::Number(n) => (
+                        (*n as usize)
+                            .checked_sub(1)
+                            .and_then(|i| groups.numbers.get(i))
+                            .copied(),
+                        n.to_string(),
+                    ),
+                    GroupRef::Name(name) => (
+                        groups
+                            .names
+                            .iter()
+                            .find(|(group_name, _)| group_name == name)
+                            .map(|(_, n)| *n),
+                        name.clone(),
+                    ),
+                    GroupRef::Relative(_) => {
+                        return Err(OutputError::FeatureNotSupported("relative backrefs"))
+                    }
+                };
+                match n {
+                    Some(n) if n <= 9 => Ok(format!("\\{n}")),
+                    Some(_) => Err(OutputError::FeatureNotSupported(
+                        "backrefs to groups after the ninth",
+                    )),
+                    // Groups are only numbered once they are output.
+                    None => Err(OutputError::UnknownGroup(name)),
+                }
+            }
+            Expr::IfGroup(_, _) => Err(OutputError::FeatureNotSupported("conditional groups")),
+            Expr::Recurse | Expr::Call(_) => Err(OutputError::FeatureNotSupported("recursion")),
+            // Groups in the text take numbers too.
+            Expr::Raw(variants) => {
+                let text = raw_variant(variants, "posix-bre")?;
+                groups.count += bre_group_count(text).unwrap_or(0);
+                Ok(text.to_string())
+            }
+            Expr::Reference(_) | Expr::Symbol(_) | Expr::Let(_, _) | Expr::MacroCall(_, _, _) => {
+                Err(OutputError::FeatureNotSupported("unresolved definitions"))
+            }
+            Expr::Scoped(_, _) => Err(OutputError::FeatureNotSupported("scoped flags")),
+        }
+    }
+
+    fn output_seq(&self, exprs: &[Expr], groups: &mut Groups) -> Result<String, OutputError> {
+        if let [e] = exprs {
+            return self.output_expr(e, groups);
+        }
+        self.output_seq_items(exprs, groups)
+    }
+
+    fn output_seq_items(&self, exprs: &[Expr], groups: &mut Groups) -> Result<String, OutputError> {
+        let items = seq_items(exprs);
+        let mut s = String::new();
+        for (i, e) in items.iter().enumerate() {
+            // Quantifiers group what they apply to themselves, so
+            // only alternations need grouping in a sequence. Every
+            // group counts towards the nine backrefs can refer to.
+            match e {
+                Expr::Or(_) | Expr::Raw(_) => {
+                    s.push_str(&self.output_group(std::slice::from_ref(*e), groups)?)
+                }
+                // `^` and `$` are only anchors at the start and end
+                // of the pattern or a group, and are literal elsewhere.
+                Expr::Assertion(Assertion::LineStart) if i > 0 => {
+                    return Err(OutputError::FeatureNotSupported(
+                        "anchors in the middle of a pattern",
+                    ))
+                }
+                Expr::Assertion(Assertion::StringStart) if i > 0 && !self.gnu => {
+                    return Err(OutputError::FeatureNotSupported(
+                        "anchors in the middle of a pattern",
+                    ))
+                }
+                Expr::Assertion(Assertion::LineEnd) if i + 1 < items.len() => {
+                    return Err(OutputError::FeatureNotSupported(
+                        "anchors in the middle of a pattern",
+                    ))
+                }
+                Expr::Assertion(Assertion::StringEnd) if i + 1 < items.len() && !self.gnu => {
+                    return Err(OutputError::FeatureNotSupported(
+                        "anchors in the middle of a pattern",
+                    ))
+                }
+                _ => s.push_str(&self.output_expr(e, groups)?),
+            }
+        }
+        Ok(s)
+    }
+
+    /// Groups expressions, which takes the next group number.
+    fn output_group(&self, exprs: &[Expr], groups: &mut Groups) -> Result<String, OutputError> {
+        groups.open();
+        Ok(format!("\\({}\\)", self.output_seq(exprs, groups)?))
+    }
+
+    fn output_set_operation(
+        &self,
+        expr: &Expr,
+        groups: &mut Groups,
+    ) -> Result<String, OutputError> {
+        match eval_set(expr, &ascii_class_set)?.map(|set| set.to_expr()) {
+            // The empty set would be a lookahead.
+            Some(Expr::Assertion(Assertion::NegativeLookAhead(exprs))) if exprs.is_empty() => {
+                Err(OutputError::FeatureNotSupported("empty character sets"))
+            }
+            Some(set) => self.output_expr(&set, groups),
+            None => Err(OutputError::FeatureNotSupported(
+                "set operations on Unicode properties",
+            )),
+        }
+    }
+
+    fn output_quantified(
+        &self,
+        exprs: &[Expr],
+        quantifier: &str,
+        groups: &mut Groups,
+    ) -> Result<String, OutputError> {
+        match exprs {
+            // Single characters, sets and groups can take a quantifier
+            // directly, but anything longer needs to be grouped.
+            [Expr::Atom(Atom::String(s))] if s.chars().count() != 1 => {
+                Ok(format!("{}{quantifier}", self.output_group(exprs, groups)?))
+            }
+            [Expr::Atom(atom)] => Ok(format!("{}{quantifier}", self.output_atom(atom)?)),
+            [e @ (Expr::Any(_)
+            | Expr::Not(_)
+            | Expr::Intersection(_)
+            | Expr::Difference(_)
+            | Expr::Group(_)
+            | Expr::GroupNamed(_, _)
+            | Expr::BackRef(_))] => Ok(format!("{}{quantifier}", self.output_expr(e, groups)?)),
+            _ => Ok(format!("{}{quantifier}", self.output_group(exprs, groups)?)),
+        }
+    }
+
+    fn output_atom(&self, atom: &Atom) -> Result<String, OutputError> {
+        match atom {
+            Atom::Char(c) => self.output_char(*c),
+            Atom::String(s) => s.chars().map(|c| self.output_char(c)).collect(),
+            Atom::CharClass(class) => match class {
+                CharClass::NotWhitespace | CharClass::NotDigit | CharClass::NotWord => {
+                    self.output_bracket(&[SetItem::CharClass(class.clone())], false)
+                }
+                _ => Ok(format!("[{}]", self.output_char_class(class)?)),
+            },
+            // Lines never contain a newline, so any character will do.
+            Atom::NotNewline => Ok(".".to_string()),
+            Atom::AnyChar => Ok(".".to_string()),
+            Atom::Grapheme => Err(OutputError::FeatureNotSupported("graphemes")),
+            Atom::NewlineSequence => Err(OutputError::FeatureNotSupported("newlines")),
+        }
+    }
+
+    fn output_char(&self, c: char) -> Result<String, OutputError> {
+        match c {
+            // Escaping anything else could make it an operator.
+            '\\' | '^' | '$' | '.' | '*' | '[' => Ok(format!("\\{c}")),
+            '\n' => Err(OutputError::FeatureNotSupported("newlines")),
+            _ => Ok(c.to_string()),
+        }
+    }
+
+    /// Outputs a bracket expression. Backslashes are not special in
+    /// one, so `]`, `^` and `-` are positioned where they are literal,
+    /// and `[` is kept away from anything that would make it start a
+    /// class.
+    fn output_bracket(&self, items: &[SetItem], negated: bool) -> Result<String, OutputError> {
+        // A negated class can only be the whole bracket expression.
+        match items {
+            [SetItem::CharClass(CharClass::NotWhitespace)] => {
+                return self.output_bracket(&[SetItem::CharClass(CharClass::Whitespace)], !negated)
+            }
+            [SetItem::CharClass(CharClass::NotDigit)] => {
+                return self.output_bracket(&[SetItem::CharClass(CharClass::Digit)], !negated)
+            }
+            [SetItem::CharClass(CharClass::NotWord)] => {
+                return self.output_bracket(&[SetItem::CharClass(CharClass::Word)], !negated)
+            }
+            _ => {}
+        }
+
+        let (mut close, mut caret, mut open, mut dash) = (false, false, false, false);
+        let mut body = String::new();
+        for item in items {
+            match item {
+                SetItem::Char(']') => close = true,
+                SetItem::Char('^') => caret = true,
+                SetItem::Char('[') => open = true,
+                SetItem::Char('-') => dash = true,
+                SetItem::Char('\n') => return Err(OutputError::FeatureNotSupported("newlines")),
+                SetItem::Char(c) => body.push(*c),
+                SetItem::Range(from, to) => body.push_str(&self.output_range(*from, *to)?),
+                SetItem::CharClass(class) => body.push_str(&self.output_char_class(class)?),
+                SetItem::Symbol(_) | SetItem::SymbolRange(_, _) => {
+                    return Err(OutputError::FeatureNotSupported("unresolved definitions"))
+                }
+            }
+        }
+
+        let mut s = String::from(if negated { "[^" } else { "[" });
+        if close {
+            s.push(']');
+        }
+        s.push_str(&body);
+        if caret {
+            // A leading `^` would negate the set.
+            s.push_str(if close || !body.is_empty() {
+                "^"
+            } else {
+                "[.^.]"
+            });
+        }
+        if open {
+            s.push('[');
+        }
+        if dash {
+            s.push('-');
+        }
+        s.push(']');
+        Ok(s)
+    }
+
+    fn output_range(&self, from: char, to: char) -> Result<String, OutputError> {
+        Ok(format!(
+            "{}-{}",
+            self.output_range_end(from)?,
+            self.output_range_end(to)?
+        ))
+    }
+
+    fn output_range_end(&self, c: char) -> Result<String, OutputError> {
+        match c {
+            ']' | '^' | '[' | '-' => Ok(format!("[.{c}.]")),
+            '\n' => Err(OutputError::FeatureNotSupported("newlines")),
+            _ => Ok(c.to_string()),
+        }
+    }
+
+    fn output_char_class(&self, class: &CharClass) -> Result<String, OutputError> {
+        match class {
+            CharClass::Whitespace => Ok("[:space:]".to_string()),
+            CharClass::Alpha => Ok("[:alpha:]".to_string()),
+            CharClass::Digit => Ok("[:digit:]".to_string()),
+            CharClass::AlphaNum => Ok("[:alnum:]".to_string()),
+            CharClass::Hex => Ok("[:xdigit:]".to_string()),
+            CharClass::LowerCase => Ok("[:lower:]".to_string()),
+            CharClass::UpperCase => Ok("[:upper:]".to_string()),
+            CharClass::Word => Ok("[:alnum:]_".to_string()),
+            CharClass::NotWhitespace | CharClass::NotDigit | CharClass::NotWord => Err(
+                OutputError::FeatureNotSupported("negated classes in character sets"),
+            ),
+            CharClass::Category(_) | CharClass::Script(_) => {
+                Err(OutputError::FeatureNotSupported("Unicode properties"))
+            }
+            CharClass::Block(from, to) => self.output_range(*from, *to),
+        }
+    }
+
+    fn output_assertion(self, assertion: &Assertion) -> Result<String, OutputError> {
+        match assertion {
+            Assertion::StringStart if self.gnu => Ok("\\`".to_string()),
+            Assertion::StringEnd if self.gnu => Ok("\\'".to_string()),
+            Assertion::WordBoundary if self.gnu => Ok("\\b".to_string()),
+            Assertion::NotWordBoundary if self.gnu => Ok("\\B".to_string()),
+            Assertion::WordStart if self.gnu => Ok("\\<".to_string()),
+            Assertion::WordEnd if self.gnu => Ok("\\>".to_string()),
+            // Tools like grep match each line as a separate string.
+            Assertion::LineStart | Assertion::StringStart => Ok("^".to_string()),
+            Assertion::LineEnd | Assertion::StringEnd => Ok("$".to_string()),
+            Assertion::StringEndBeforeNewline => Err(OutputError::FeatureNotSupported(
+                "end of string before a newline",
+            )),
+            Assertion::WordBoundary
+            | Assertion::NotWordBoundary
+            | Assertion::WordStart
+            | Assertion::WordEnd => Err(OutputError::FeatureNotSupported("word boundaries")),
+            Assertion::SymbolStart | Assertion::SymbolEnd => {
+                Err(OutputError::FeatureNotSupported("symbol boundaries"))
+            }
+            Assertion::LookAhead(_)
+            | Assertion::NegativeLookAhead(_)
+            | Assertion::LookBehind(_)
+            | Assertion::NegativeLookBehind(_) => {
+                Err(OutputError::FeatureNotSupported("lookarounds"))
+            }
+        }
+    }
+}
+
+/// The items of a sequence, with nested sequences flattened.
+fn seq_items(exprs: &[Expr]) -> Vec<&Expr> {
+    exprs
+        .iter()
+        .flat_map(|e| match e {
+            Expr::Seq(exprs) => seq_items(exprs),
+            _ => vec![e],
+        })
+        .collect()
+}
+
+fn has_anchors(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Assertion(
+            Assertion::LineStart
+                | Assertion::LineEnd
+                | Assertion::StringStart
+                | Assertion::StringEnd
+        )
+    ) || expr.children().iter().any(has_anchors)
+}
+
+fn has_groups(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Group(_) | Expr::GroupN(_, _) | Expr::GroupNamed(_, _)
+    ) || expr.children().iter().any(has_groups)
+}
@@ -0,0 +1,279 @@
+use crate::expr::{Assertion, Atom, CharClass, Expr, SetItem};
+use crate::output::set::{ascii_class_set, eval_set};
+use crate::output::{check_no_recursion, raw_variant, Output, OutputError};
+
+/// POSIX extended regular expressions, as used by `grep -E` and awk.
+/// There are no escape sequences, so control characters are output
+/// as they are, except for newlines: grep splits its pattern on them
+/// and awk regex literals cannot contain them.
+#[derive(Copy, Clone, Default)]
+pub struct PosixEreOutput {}
+
+impl Output for PosixEreOutput {
+    fn output(&self, expr: &Expr) -> Result<String, OutputError> {
+        check_no_recursion(expr)?;
+        self.output_expr(expr)
+    }
+}
+
+impl PosixEreOutput {
+    fn output_expr(&self, expr: &Expr) -> Result<String, OutputError> {
+        match expr {
+            Expr::Atom(c) => self.output_atom(c),
+            Expr::Seq(exprs) => self.output_seq(exprs),
+            Expr::Or(exprs) if exprs.len() == 1 => self.output_expr(exprs.first().expect("")),
+            Expr::Or(exprs) => {
+                let mut s = self.output_expr(exprs.first().expect(""))?;
+                for e in exprs[1..].iter() {
+                    s.push('|');
+                    s.push_str(&self.output_expr(e)?);
+                }
+                Ok(s)
+            }
+            Expr::ZeroOrOne(exprs) => self.output_quantified(exprs, "?"),
+            Expr::ZeroOrMore(exprs) => self.output_quantified(exprs, "*"),
+            Expr::OneOrMore(exprs) => self.output_quantified(exprs, "+"),
+            Expr::Exactly(n, exprs) => self.output_quantified(exprs, &format!("{{{n}}}")),
+            Expr::AtLeast(n, exprs) => self.output_quantified(exprs, &format!("{{{n},}}")),
+            Expr::Between(n, m, exprs) => self.output_quantified(exprs, &format!("{{{n},{m}}}")),
+            Expr::ZeroOrOneReluctant(_)
+            | Expr::ZeroOrMoreReluctant(_)
+            | Expr::OneOrMoreReluctant(_)
+            | Expr::AtLeastReluctant(_, _)
+            | Expr::BetweenReluctant(_, _, _) => {
+                Err(OutputError::FeatureNotSupported("reluctant quantifiers"))
+            }
+            Expr::ZeroOrOnePossessive(_)
+            | Expr::ZeroOrMorePossessive(_)
+            | Expr::OneOrMorePossessive(_)
+            | Expr::ExactlyPossessive(_, _)
+            | Expr::AtLeastPossessive(_, _)
+            | Expr::BetweenPossessive(_, _, _) => {
+                Err(OutputError::FeatureNotSupported("possessive quantifiers"))
+            }
+            Expr::Atomic(_) => Err(OutputError::FeatureNotSupported("atomic groups")),
+            Expr::Assertion(assertion) => self.output_assertion(assertion),
+            Expr::Intersection(_) | Expr::Difference(_) => self.output_set_operation(expr),
+            Expr::Any(items) => self.output_bracket(items, false),
+            Expr::Not(items) => self.output_bracket(items, true),
+            Expr::Group(exprs) => Ok(format!("({})", self.output_seq(exprs)?)),
+            // Every group captures, so group numbers cannot be chosen.
+            Expr::GroupN(_, _) | Expr::GroupNamed(_, _) => {
+                Err(OutputError::FeatureNotSupported("named capture groups"))
+            }
+            Expr::BackRef(_) => Err(OutputError::FeatureNotSupported("backrefs")),
+            Expr::IfGroup(_, _) => Err(OutputError::FeatureNotSupported("conditional groups")),
+            Expr::Recurse | Expr::Call(_) => Err(OutputError::FeatureNotSupported("recursion")),
+            Expr::Raw(variants) => Ok(raw_variant(variants, "posix-ere")?.to_string()),
+            Expr::Reference(_) | Expr::Symbol(_) | Expr::Let(_, _) | Expr::MacroCall(_, _, _) => {
+                Err(OutputError::FeatureNotSupported("unresolved definitions"))
+            }
+            Expr::Scoped(_, _) => Err(OutputError::FeatureNotSupported("scoped flags")),
+        }
+    }
+
+    fn output_seq(&self, exprs: &[Expr]) -> Result<String, OutputError> {
+        if let [e] = exprs {
+            return self.output_expr(e);
+        }
+        self.output_seq_items(exprs)
+    }
+
+    fn output_seq_items(&self, exprs: &[Expr]) -> Result<String, OutputError> {
+        let mut s = String::new();
+        for e in exprs {
+            // Quantifiers group what they apply to themselves, so
+            // only alternations need grouping in a sequence. There
+            // are no non-capturing groups, so this uses plain ones.
+            match e {
+                Expr::Or(_) | Expr::Raw(_) => s.push_str(&format!("({})", self.output_expr(e)?)),
+                Expr::Seq(exprs) => s.push_str(&self.output_seq_items(exprs)?),
+                _ => s.push_str(&self.output_expr(e)?),
+            }
+        }
+        Ok(s)
+    }
+
+    fn output_set_operation(&self, expr: &Expr) -> Result<String, OutputError> {
+        match eval_set(expr, &ascii_class_set)?.map(|set| set.to_expr()) {
+            // The empty set would be a lookahead.
+            Some(Expr::Assertion(Assertion::NegativeLookAhead(exprs))) if exprs.is_empty() => {
+                Err(OutputError::FeatureNotSupported("empty character sets"))
+            }
+            Some(set) => self.output_expr(&set),
+            None => Err(OutputError::FeatureNotSupported(
+                "set operations on Unicode properties",
+            )),
+        }
+    }
+
+    fn output_quantified(&self, exprs: &[Expr], quantifier: &str) -> Result<String, OutputError> {
+        match exprs {
+            // Single characters, sets and groups can take a quantifier
+            // directly, but anything longer needs to be grouped.
+            [Expr::Atom(Atom::String(s))] if s.chars().count() != 1 => {
+                Ok(format!("({}){quantifier}", self.output_seq(exprs)?))
+            }
+            [Expr::Atom(atom)] => Ok(format!("{}{quantifier}", self.output_atom(atom)?)),
+            [e @ (Expr::Any(_)
+            | Expr::Not(_)
+            | Expr::Intersection(_)
+            | Expr::Difference(_)
+            | Expr::Group(_))] => Ok(format!("{}{quantifier}", self.output_expr(e)?)),
+            _ => Ok(format!("({}){quantifier}", self.output_seq(exprs)?)),
+        }
+    }
+
+    fn output_atom(&self, atom: &Atom) -> Result<String, OutputError> {
+        match atom {
+            Atom::Char(c) => self.output_char(*c),
+            Atom::String(s) => s.chars().map(|c| self.output_char(c)).collect(),
+            Atom::CharClass(class) => match class {
+                CharClass::NotWhitespace | CharClass::NotDigit | CharClass::NotWord => {
+                    self.output_bracket(&[SetItem::CharClass(class.clone())], false)
+                }
+                _ => Ok(format!("[{}]", self.output_char_class(class)?)),
+            },
+            // Lines never contain a newline, so any character will do.
+            Atom::NotNewline => Ok(".".to_string()),
+            Atom::AnyChar => Ok(".".to_string()),
+            Atom::Grapheme => Err(OutputError::FeatureNotSupported("graphemes")),
+            Atom::NewlineSequence => Err(OutputError::FeatureNotSupported("newlines")),
+        }
+    }
+
+    fn output_char(&self, c: char) -> Result<String, OutputError> {
+        match c {
+            // `]` and `}` are literal on their own, and escaping them
+            // is undefined.
+            '\\' | '^' | '$' | '.' | '|' | '?' | '*' | '+' | '(' | ')' | '[' | '{' => {
+                Ok(format!("\\{c}"))
+            }
+            '\n' => Err(OutputError::FeatureNotSupported("newlines")),
+            _ => Ok(c.to_string()),
+        }
+    }
+
+    /// Outputs a bracket expression. Backslashes are not special in
+    /// one, so `]`, `^` and `-` are positioned where they are literal,
+    /// and `[` is kept away from anything that would make it start a
+    /// class. awk does treat a backslash as an escape though, so it
+    /// is doubled, which grep takes as the same character twice.
+    fn output_bracket(&self, items: &[SetItem], negated: bool) -> Result<String, OutputError> {
+        // A negated class can only be the whole bracket expression.
+        match items {
+            [SetItem::CharClass(CharClass::NotWhitespace)] => {
+                return self.output_bracket(&[SetItem::CharClass(CharClass::Whitespace)], !negated)
+            }
+            [SetItem::CharClass(CharClass::NotDigit)] => {
+                return self.output_bracket(&[SetItem::CharClass(CharClass::Digit)], !negated)
+            }
+            [SetItem::CharClass(CharClass::NotWord)] => {
+                return self.output_bracket(&[SetItem::CharClass(CharClass::Word)], !negated)
+            }
+            _ => {}
+        }
+
+        let (mut close, mut caret, mut open, mut dash) = (false, false, false, false);
+        let mut body = String::new();
+        for item in items {
+            match item {
+                SetItem::Char(']') => close = true,
+                SetItem::Char('^') => caret = true,
+                SetItem::Char('[') => open = true,
+                SetItem::Char('-') => dash = true,
+                SetItem::Char('\n') => return Err(OutputError::FeatureNotSupported("newlines")),
+                SetItem::Char('\\') => body.push_str("\\\\"),
+                SetItem::Char(c) => body.push(*c),
+                SetItem::Range(from, to) => body.push_str(&self.output_range(*from, *to)?),
+                SetItem::CharClass(class) => body.push_str(&self.output_char_class(class)?),
+                SetItem::Symbol(_) | SetItem::SymbolRange(_, _) => {
+                    return Err(OutputError::FeatureNotSupported("unresolved definitions"))
+                }
+            }
+        }
+
+        let mut s = String::from(if negated { "[^" } else { "[" });
+        if close {
+            s.push(']');
+        }
+        s.push_str(&body);
+        if caret {
+            // A leading `^` would negate the set.
+            s.push_str(if close || !body.is_empty() {
+                "^"
+            } else {
+                "[.^.]"
+            });
+        }
+        if open {
+            s.push('[');
+        }
+        if dash {
+            s.push('-');
+        }
+        s.push(']');
+        Ok(s)
+    }
+
+    fn output_range(&self, from: char, to: char) -> Result<String, OutputError> {
+        Ok(format!(
+            "{}-{}",
+            self.output_range_end(from)?,
+            self.output_range_end(to)?
+        ))
+    }
+
+    fn output_range_end(&self, c: char) -> Result<String, OutputError> {
+        match c {
+            ']' | '^' | '[' | '-' => Ok(format!("[.{c}.]")),
+            '\\' => Ok("\\\\".to_string()),
+            '\n' => Err(OutputError::FeatureNotSupported("newlines")),
+            _ => Ok(c.to_string()),
+        }
+    }
+
+    fn output_char_class(&self, class: &CharClass) -> Result<String, OutputError> {
+        match class {
+            CharClass::Whitespace => Ok("[:space:]".to_string()),
+            CharClass::Alpha => Ok("[:alpha:]".to_string()),
+            CharClass::Digit => Ok("[:digit:]".to_string()),
+            CharClass::AlphaNum => Ok("[:alnum:]".to_string()),
+            CharClass::Hex => Ok("[:xdigit:]".to_string()),
+            CharClass::LowerCase => Ok("[:lower:]".to_string()),
+            CharClass::UpperCase => Ok("[:upper:]".to_string()),
+            CharClass::Word => Ok("[:alnum:]_".to_string()),
+            CharClass::NotWhitespace | CharClass::NotDigit | CharClass::NotWord => Err(
+                OutputError::FeatureNotSupported("negated classes in character sets"),
+            ),
+            CharClass::Category(_) | CharClass::Script(_) => {
+                Err(OutputError::FeatureNotSupported("Unicode properties"))
+            }
+            CharClass::Block(from, to) => self.output_range(*from, *to),
+        }
+    }
+
+    fn output_assertion(self, assertion: &Assertion) -> Result<String, OutputError> {
+        match assertion {
+            // Tools like grep match each line as a separate string.
+            Assertion::LineStart | Assertion::StringStart => Ok("^".to_string()),
+            Assertion::LineEnd | Assertion::StringEnd => Ok("$".to_string()),
+            Assertion::StringEndBeforeNewline => Err(OutputError::FeatureNotSupported(
+                "end of string before a newline",
+            )),
+            Assertion::WordBoundary
+            | Assertion::NotWordBoundary
+            | Assertion::WordStart
+            | Assertion::WordEnd => Err(OutputError::FeatureNotSupported("word boundaries")),
+            Assertion::SymbolStart | Assertion::SymbolEnd => {
+                Err(OutputError::FeatureNotSupported("symbol boundaries"))
+            }
+            Assertion::LookAhead(_)
+            | Assertion::NegativeLookAhead(_)
+            | Assertion::LookBehind(_)
+            | Assertion::NegativeLookBehind(_) => {
+                Err(OutputError::FeatureNotSupported("lookarounds"))
+            }
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use crate::expr::{seq_width, Assertion, Atom, CharClass, Expr, Flag, GroupRef, SetItem};
+use crate::output::set::{
+    ascii_class_set, case_insensitive_set_operations, eval_set, set_operation_lookarounds, CharSet,
+};
+use crate::output::{check_group_refs, check_no_recursion, raw_variant, Output, OutputError};
+
+#[derive(Copy, Clone, Default)]
+pub struct PythonOutput {
+    /// Whether to output a raw string literal rather than the bare
+    /// pattern.
+    pub raw_string: bool,
+}
+
+impl Output for PythonOutput {
+    fn output(&self, expr: &Expr) -> Result<String, OutputError> {
+        check_no_recursion(expr)?;
+        check_group_refs(expr)?;
+        let mut expr = expr.clone();
+        case_insensitive_set_operations(&mut expr, false);
+        let pattern = self.output_expr(&expr)?;
+        if self.raw_string {
+            Ok(raw_string_literal(&pattern))
+        } else {
+            Ok(pattern)
+        }
+    }
+}
+
+impl PythonOutput {
+    fn output_expr(&self, expr: &Expr) -> Result<String, OutputError> {
+        match expr {
+            Expr::Atom(c) => self.output_atom(c),
+            Expr::Seq(exprs) => self.output_seq(exprs),
+            Expr::Or(exprs) if exprs.len() == 1 => self.output_expr(exprs.first().expect("")),
+            Expr::Or(exprs) => {
+                let mut s = self.output_expr(exprs.first().expect(""))?;
+                for e in exprs[1..].iter() {
+                    s.push('|');
+                    s.push_str(&self.output_expr(e)?);
+                }
+                Ok(s)
+            }
+            Expr::ZeroOrOne(exprs) => self.output_quantified(exprs, "?"),
+            Expr::ZeroOrOneReluctant(exprs) => self.output_quantified(exprs, "??"),
+            Expr::ZeroOrMore(exprs) => self.output_quantified(exprs, "*"),
+            Expr::ZeroOrMoreReluctant(exprs) => self.output_quantified(exprs, "*?"),
+            Expr::OneOrMore(exprs) => self.output_quantified(exprs, "+"),
+            Expr::OneOrMoreReluctant(exprs) => self.output_quantified(exprs, "+?"),
+            Expr::Exactly(n, exprs) => self.output_counted(exprs, &format!("{{{n}}}")),
+            Expr::AtLeast(n, exprs) => self.output_counted(exprs, &format!("{{{n},}}")),
+            Expr::AtLeastReluctant(n, exprs) => self.output_counted(exprs, &format!("{{{n},}}?")),
+            Expr::Between(n, m, exprs) => self.output_counted(exprs, &format!("{{{n},{m}}}")),
+            Expr::BetweenReluctant(n, m, exprs) => {
+                self.output_counted(exprs, &format!("{{{n},{m}}}?"))
+            }
+            // Possessive quantifiers and atomic groups need Python 3.11.
+            Expr::ZeroOrOnePossessive(_)
+            | Expr::ZeroOrMorePossessive(_)
+            | Expr::OneOrMorePossessive(_)
+            | Expr::ExactlyPossessive(_, _)
+            | Expr::AtLeastPossessive(_, _)
+            | Expr::BetweenPossessive(_, _, _) => {
+                Err(OutputError::FeatureNotSupported("possessive quantifiers"))
+            }
+            Expr::Atomic(_) => Err(OutputError::FeatureNotSupported("atomic groups")),
+            Expr::Assertion(assertion) => self.output_assertion(assertion),
+            Expr::Intersection(_) | Expr::Difference(_) => self.output_set_operation(expr),
+            Expr::Any(items) => {
+                let mut s = String::from("[");
+                for item in items {
+                    s.push_str(&self.output_set_item(item)?);
+                }
+                s.push(']');
+                Ok(s)
+            }
+            Expr::Not(items) => {
+                let mut s = String::from("[^");
+                for item in items {
+                    s.push_str(&self.output_set_item(item)?);
+                }
+                s.push(']');
+                Ok(s)
+            }
+            Expr::Group(exprs) => Ok(format!("({})", self.output_seq(exprs)?)),
+            Expr::GroupN(n, exprs) => Ok(format!("(?P<n{n}>{})", self.output_seq(exprs)?)),
+            Expr::GroupNamed(name, exprs) => Ok(format!("(?P<{name}>{})", self.output_seq(exprs)?)),
+            Expr::BackRef(GroupRef::Number(n)) => Ok(format!("\\{n}")),
+            Expr::BackRef(GroupRef::Name(name)) => Ok(format!("(?P={name})")),
+            Expr::BackRef(GroupRef::Relative(_)) => {
+                Err(OutputError::FeatureNotSupported("relative backrefs"))
+            }
+            Expr::Recurse | Expr::Call(_) => Err(OutputError::FeatureNotSupported("recursion")),
+            Expr::IfGroup(group, exprs) => {
+                let condition = match group {
+                    GroupRef::Number(n) => n.to_string(),
+                    GroupRef::Name(name) => name.clone(),
+                    GroupRef::Relative(_) => {
+                        return Err(OutputError::FeatureNotSupported(
+                            "relative conditional groups",
+                        ))
+                    }
+                };
+                let mut s = format!("(?({condition})");
+                for (idx, e) in exprs.iter().enumerate() {
+                    if idx > 0 {
+                        s.push('|');
+                    }
+                    // An alternation would add more branches.
+                    match e {
+                        Expr::Or(_) => s.push_str(&format!("(?:{})", self.output_expr(e)?)),
+                        _ => s.push_str(&self.output_expr(e)?),
+                    }
+                }
+                s.push(')');
+                Ok(s)
+            }
+            Expr::Raw(variants) => Ok(raw_variant(variants, "python")?.to_string()),
+            Expr::Reference(_) | Expr::Symbol(_) | Expr::Let(_, _) | Expr::MacroCall(_, _, _) => {
+                Err(OutputError::FeatureNotSupported("unresolved definitions"))
+            }
+            Expr::Scoped(flag, exprs) => {
+                let flag = match flag {
+                    Flag::CaseInsensitive => "i",
+                    Flag::CaseSensitive => "-i",
+                    Flag::Multiline => "m",
+                    Flag::DotAll => "s",
+                };
+                Ok(format!("(?{flag}:{})", self.output_seq(exprs)?))
+            }
+        }
+    }
+
+    fn output_seq(&self, exprs: &[Expr]) -> Result<String, OutputError> {
+        if let [e] = exprs {
+            return self.output_expr(e);
+        }
+        self.output_seq_items(exprs)
+    }
+
+    fn output_seq_items(&self, exprs: &[Expr]) -> Result<String, OutputError> {
+        let mut s = String::new();
+        for e in exprs {
+            // NB In a sequence, we need to avoid mushing
+            // together several tokens that will then get
+            // affected by a postfix modifier.
+            match e {
+                Expr::Atom(_) => s.push_str(&self.output_expr(e)?),
+                Expr::Assertion(_) => s.push_str(&self.output_expr(e)?),
+                Expr::Any(_) | Expr::Not(_) | Expr::Intersection(_) | Expr::Difference(_) => {
+                    s.push_str(&self.output_expr(e)?)
+                }
+                Expr::Seq(exprs) => s.push_str(&self.output_seq_items(exprs)?),
+                _ => s.push_str(&format!("(?:{})", self.output_expr(e)?)),
+            }
+        }
+        Ok(s)
+    }
+
+    fn output_set_operation(&self, expr: &Expr) -> Result<String, OutputError> {
+        match eval_set(expr, &class_set)? {
+            Some(set) => self.output_expr(&set.to_expr()),
+            None => self.output_seq_items(&set_operation_lookarounds(expr)),
+        }
+    }
+
+    fn output_quantified(&self, exprs: &[Expr], quantifier: &str) -> Result<String, OutputError> {
+        match exprs {
+            // Single characters can take a quantifier directly, but
+            // anything longer needs to be grouped.
+            [Expr::Atom(Atom::String(s))] if s.chars().count() != 1 => {
+                Ok(format!("(?:{}){quantifier}", self.output_seq(exprs)?))
+            }
+            [Expr::Atom(atom)] => Ok(format!("{}{quantifier}", self.output_atom(atom)?)),
+            _ => Ok(format!("(?:{}){quantifier}", self.output_seq(exprs)?)),
+        }
+    }
+
+    fn output_counted(&self, exprs: &[Expr], quantifier: &str) -> Result<String, OutputError> {
+        Ok(format!("(?:{}){quantifier}", self.output_seq(exprs)?))
+    }
+
+    fn output_atom(&self, atom: &Atom) -> Result<String, OutputError> {
+        match atom {
+            Atom::Char(c) => Ok(self.output_char(*c)),
+            Atom::String(s) => Ok(s.chars().map(|c| self.output_char(c)).collect()),
+            Atom::CharClass(class) => Ok(format!("[{}]", self.output_char_class(class)?)),
+            // `.` would also match a newline in a dotall scope.
+            Atom::NotNewline => Ok("[^\\n]".to_string()),
+            Atom::AnyChar => Ok("(?s:.)".to_string()),
+            Atom::Grapheme => Err(OutputError::FeatureNotSupported("graphemes")),
+            Atom::NewlineSequence => {
+                Ok("(?:\\r\\n|[\\n\\x0b\\f\\r\\x85\\u2028\\u2029])".to_string())
+            }
+        }
+    }
+
+    fn output_char(&self, c: char) -> String {
+        match c {
+            '\\' | '^' | '$' | '.' | '|' | '?' | '*' | '+' | '(' | ')' | '[' | ']' | '{' | '}' => {
+                format!("\\{c}")
+            }
+            '\n' => "\\n".to_string(),
+            '\r' => "\\r".to_string(),
+            '\t' => "\\t".to_string(),
+            _ if c.is_control() => escape_code_point(c),
+            _ => c.to_string(),
+        }
+    }
+
+    fn output_set_item(&self, item: &SetItem) -> Result<String, OutputError> {
+        match item {
+            SetItem::Char(c) => self.output_set_char(*c),
+            SetItem::Range(from, to) => self.output_range(*from, *to),
+            SetItem::CharClass(class) => self.output_char_class(class),
+            SetItem::Symbol(_) | SetItem::SymbolRange(_, _) => {
+                Err(OutputError::FeatureNotSupported("unresolved definitions"))
+            }
+        }
+    }
+
+    fn output_range(&self, from: char, to: char) -> Result<String, OutputError> {
+        Ok(format!(
+            "{}-{}",
+            self.output_set_char(from)?,
+            self.output_set_char(to)?
+        ))
+    }
+
+    fn output_set_char(&self, c: char) -> Result<String, OutputError> {
+        match c {
+            // Python warns about `[`, `&&`, `~~` and `||` in sets, as
+            // they may become set syntax.
+            ']' | '[' | '\\' | '^' | '-' | '&' | '~' | '|' => Ok(format!("\\{c}")),
+            '\n' => Ok("\\n".to_string()),
+            '\r' => Ok("\\r".to_string()),
+            '\t' => Ok("\\t".to_string()),
+            _ if c.is_ascii() && !c.is_ascii_control() => Ok(c.to_string()),
+            _ => Ok(escape_code_point(c)),
+        }
+    }
+
+    fn output_char_class(&self, class: &CharClass) -> Result<String, OutputError> {
+        match class {
+            CharClass::Whitespace => Ok("\\s".to_string()),
+            CharClass::Alpha => Ok("a-zA-Z".to_string()),
+            CharClass::Digit => Ok("\\d".to_string()),
+            CharClass::AlphaNum => Ok("0-9a-zA-Z".to_string()),
+            CharClass::Hex => Ok("0-9a-fA-F".to_string()),
+            CharClass::LowerCase => Ok("a-z".to_string()),
+            CharClass::UpperCase => Ok("A-Z".to_string()),
+            CharClass::Word => Ok("\\w".to_string()),
+            CharClass::NotWhitespace => Ok("\\S".to_string()),
+            CharClass::NotDigit => Ok("\\D".to_string()),
+            CharClass::NotWord => Ok("\\W".to_string()),
+            CharClass::Category(_) | CharClass::Script(_) => {
+                Err(OutputError::FeatureNotSupported("Unicode properties"))
+            }
+            CharClass::Block(from, to) => self.output_range(*from, *to),
+        }
+    }
+
+    fn output_assertion(self, assertion: &Assertion) -> Result<String, OutputError> {
+        match assertion {
+            Assertion::LineStart => Ok("^".to_string()),
+            Assertion::LineEnd => Ok("$".to_string()),
+            Assertion::StringStart => Ok("\\A".to_string()),
+            // Python's `\Z` is the very end of the string.
+            Assertion::StringEnd => Ok("\\Z".to_string()),
+            Assertion::StringEndBeforeNewline => Ok("(?=\\n?\\Z)".to_string()),
+            Assertion::WordBoundary => Ok("\\b".to_string()),
+            Assertion::NotWordBoundary => Ok("\\B".to_string()),
+            Assertion::WordStart => Ok("\\b(?=\\w)".to_string()),
+            Assertion::WordEnd => Ok("\\b(?<=\\w)".to_string()),
+            Assertion::SymbolStart => Ok("(?<![\\w$])(?=[\\w$])".to_string()),
+            Assertion::SymbolEnd => Ok("(?<=[\\w$])(?![\\w$])".to_string()),
+            Assertion::LookAhead(exprs) => Ok(format!("(?={})", self.output_seq(exprs)?)),
+            Assertion::NegativeLookAhead(exprs) => Ok(format!("(?!{})", self.output_seq(exprs)?)),
+            Assertion::LookBehind(exprs) => Ok(format!("(?<={})", self.output_look_behind(exprs)?)),
+            Assertion::NegativeLookBehind(exprs) => {
+                Ok(format!("(?<!{})", self.output_look_behind(exprs)?))
+            }
+        }
+    }
+
+    fn output_look_behind(self, exprs: &[Expr]) -> Result<String, OutputError> {
+        // Python requires a lookbehind to have a fixed length, even
+        // across alternatives.
+        let (min, max) = seq_width(exprs);
+        if Some(min) != max {
+            return Err(OutputError::FeatureNotSupported(
+                "variable-length lookbehind",
+            ));
+        }
+        self.output_seq(exprs)
+    }
+}
+
+/// The characters in a class. Unlike in other flavors, `\d`, `\s` and
+/// `\w` match Unicode characters, so their sets are not known.
+fn class_set(class: &CharClass) -> Option<CharSet> {
+    match class {
+        CharClass::Whitespace
+        | CharClass::Digit
+        | CharClass::Word
+        | CharClass::NotWhitespace
+        | CharClass::NotDigit
+        | CharClass::NotWord => None,
+        _ => ascii_class_set(class),
+    }
+}
+
+fn escape_code_point(c: char) -> String {
+    match c as u32 {
+        n if n <= 0xFF => format!("\\x{n:02x}"),
+        n if n <= 0xFFFF => format!("\\u{n:04x}"),
+        n => format!("\\U{n:08x}"),
+    }
+}
+
+/// Quotes a pattern as a raw string literal. Quotes are escaped, which
+/// leaves a backslash in the pattern that does not change its meaning.
+/// Backslashes in the pattern always come in escape sequences, so it
+/// cannot end with one that would escape the closing quote.
+fn raw_string_literal(pattern: &str) -> String {
+    let mut s = String::from("r\"");
+    let mut escaped = false;
+    for c in pattern.chars() {
+        match c {
+            '"' if !escaped => s.push_str("\\\""),
+            '\n' if escaped => s.push('n'),
+            '\n' => s.push_str("\\n"),
+            '\r' if escaped => s.push('r'),
+            '\r' => s.push_str("\\r"),
+            _ => s.push(c),
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    s.push('"');
+    s
+}
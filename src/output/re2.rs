@@ -0,0 +1,344 @@
+use crate::expr::{Assertion, Atom, CharClass, Expr, Flag, SetItem};
+use crate::output::set::{ascii_class_set, eval_set, has_case_insensitive_set_operations, CharSet};
+use crate::output::{check_no_recursion, raw_variant, Output, OutputError};
+
+/// The most repetitions RE2 allows in a counted repetition.
+const MAX_REPEAT: u32 = 1000;
+
+#[derive(Copy, Clone, Default)]
+pub struct RE2Output {}
+
+impl Output for RE2Output {
+    fn output(&self, expr: &Expr) -> Result<String, OutputError> {
+        check_no_recursion(expr)?;
+        check_nested_repetitions(expr, 1)?;
+        // The set is computed without regard to case, and there are
+        // no lookaheads to fall back on.
+        if has_case_insensitive_set_operations(expr, false) {
+            return Err(OutputError::FeatureNotSupported(
+                "set operations in case-insensitive scopes",
+            ));
+        }
+        self.output_expr(expr)
+    }
+}
+
+impl RE2Output {
+    fn output_expr(&self, expr: &Expr) -> Result<String, OutputError> {
+        match expr {
+            Expr::Atom(c) => self.output_atom(c),
+            Expr::Seq(exprs) => self.output_seq(exprs),
+            Expr::Or(exprs) if exprs.len() == 1 => self.output_expr(exprs.first().expect("")),
+            Expr::Or(exprs) => {
+                let mut s = self.output_expr(exprs.first().expect(""))?;
+                for e in exprs[1..].iter() {
+                    s.push('|');
+                    s.push_str(&self.output_expr(e)?);
+                }
+                Ok(s)
+            }
+            Expr::ZeroOrOne(exprs) => self.output_quantified(exprs, "?"),
+            Expr::ZeroOrOneReluctant(exprs) => self.output_quantified(exprs, "??"),
+            Expr::ZeroOrMore(exprs) => self.output_quantified(exprs, "*"),
+            Expr::ZeroOrMoreReluctant(exprs) => self.output_quantified(exprs, "*?"),
+            Expr::OneOrMore(exprs) => self.output_quantified(exprs, "+"),
+            Expr::OneOrMoreReluctant(exprs) => self.output_quantified(exprs, "+?"),
+            Expr::Exactly(n, exprs) => self.output_counted(exprs, *n, Some(*n), ""),
+            Expr::AtLeast(n, exprs) => self.output_counted(exprs, *n, None, ""),
+            Expr::AtLeastReluctant(n, exprs) => self.output_counted(exprs, *n, None, "?"),
+            Expr::Between(n, m, exprs) => self.output_counted(exprs, *n, Some(*m), ""),
+            Expr::BetweenReluctant(n, m, exprs) => self.output_counted(exprs, *n, Some(*m), "?"),
+            Expr::ZeroOrOnePossessive(_)
+            | Expr::ZeroOrMorePossessive(_)
+            | Expr::OneOrMorePossessive(_)
+            | Expr::ExactlyPossessive(_, _)
+            | Expr::AtLeastPossessive(_, _)
+            | Expr::BetweenPossessive(_, _, _) => {
+                Err(OutputError::FeatureNotSupported("possessive quantifiers"))
+            }
+            Expr::Atomic(_) => Err(OutputError::FeatureNotSupported("atomic groups")),
+            Expr::Assertion(assertion) => self.output_assertion(assertion),
+            Expr::Intersection(_) | Expr::Difference(_) => self.output_set_operation(expr),
+            Expr::Any(items) => {
+                let mut s = String::from("[");
+                for item in items {
+                    s.push_str(&self.output_set_item(item)?);
+                }
+                s.push(']');
+                Ok(s)
+            }
+            Expr::Not(items) => {
+                let mut s = String::from("[^");
+                for item in items {
+                    s.push_str(&self.output_set_item(item)?);
+                }
+                s.push(']');
+                Ok(s)
+            }
+            Expr::Group(exprs) => Ok(format!("({})", self.output_seq(exprs)?)),
+            Expr::GroupN(n, exprs) => Ok(format!("(?P<n{n}>{})", self.output_seq(exprs)?)),
+            Expr::GroupNamed(name, exprs) => Ok(format!("(?P<{name}>{})", self.output_seq(exprs)?)),
+            Expr::BackRef(_) => Err(OutputError::FeatureNotSupported("backrefs")),
+            Expr::IfGroup(_, _) => Err(OutputError::FeatureNotSupported("conditional groups")),
+            Expr::Recurse | Expr::Call(_) => Err(OutputError::FeatureNotSupported("recursion")),
+            Expr::Raw(variants) => Ok(raw_variant(variants, "re2")?.to_string()),
+            Expr::Reference(_) | Expr::Symbol(_) | Expr::Let(_, _) | Expr::MacroCall(_, _, _) => {
+                Err(OutputError::FeatureNotSupported("unresolved definitions"))
+            }
+            Expr::Scoped(flag, exprs) => {
+                let flag = match flag {
+                    Flag::CaseInsensitive => "i",
+                    Flag::CaseSensitive => "-i",
+                    Flag::Multiline => "m",
+                    Flag::DotAll => "s",
+                };
+                Ok(format!("(?{flag}:{})", self.output_seq(exprs)?))
+            }
+        }
+    }
+
+    fn output_seq(&self, exprs: &[Expr]) -> Result<String, OutputError> {
+        if let [e] = exprs {
+            return self.output_expr(e);
+        }
+        self.output_seq_items(exprs)
+    }
+
+    fn output_seq_items(&self, exprs: &[Expr]) -> Result<String, OutputError> {
+        let mut s = String::new();
+        for e in exprs {
+            // NB In a sequence, we need to avoid mushing
+            // together several tokens that will then get
+            // affected by a postfix modifier.
+            match e {
+                Expr::Atom(_) => s.push_str(&self.output_expr(e)?),
+                Expr::Assertion(_) => s.push_str(&self.output_expr(e)?),
+                Expr::Any(_) | Expr::Not(_) | Expr::Intersection(_) | Expr::Difference(_) => {
+                    s.push_str(&self.output_expr(e)?)
+                }
+                Expr::Seq(exprs) => s.push_str(&self.output_seq_items(exprs)?),
+                _ => s.push_str(&format!("(?:{})", self.output_expr(e)?)),
+            }
+        }
+        Ok(s)
+    }
+
+    fn output_set_operation(&self, expr: &Expr) -> Result<String, OutputError> {
+        match eval_set(expr, &class_set)?.map(|set| set.to_expr()) {
+            // The empty set would be a lookahead.
+            Some(Expr::Assertion(Assertion::NegativeLookAhead(exprs))) if exprs.is_empty() => {
+                Ok("[^\\x00-\\x{10FFFF}]".to_string())
+            }
+            Some(set) => self.output_expr(&set),
+            None => Err(OutputError::FeatureNotSupported(
+                "set operations on Unicode properties",
+            )),
+        }
+    }
+
+    fn output_quantified(&self, exprs: &[Expr], quantifier: &str) -> Result<String, OutputError> {
+        match exprs {
+            // Single characters can take a quantifier directly, but
+            // anything longer needs to be grouped.
+            [Expr::Atom(Atom::String(s))] if s.chars().count() != 1 => {
+                Ok(format!("(?:{}){quantifier}", self.output_seq(exprs)?))
+            }
+            [Expr::Atom(atom)] => Ok(format!("{}{quantifier}", self.output_atom(atom)?)),
+            _ => Ok(format!("(?:{}){quantifier}", self.output_seq(exprs)?)),
+        }
+    }
+
+    /// RE2 allows at most 1000 repetitions, so larger counts are split
+    /// into several repetitions in sequence.
+    fn output_counted(
+        &self,
+        exprs: &[Expr],
+        min: u32,
+        max: Option<u32>,
+        suffix: &str,
+    ) -> Result<String, OutputError> {
+        if let Some(max) = max.filter(|max| *max < min) {
+            return Err(OutputError::ReversedRepetition(min, max));
+        }
+        let mut counts = vec![];
+        let (mut min, mut max) = (min, max);
+        while min > MAX_REPEAT || max.is_some_and(|max| max > MAX_REPEAT) {
+            if min > MAX_REPEAT {
+                counts.push((MAX_REPEAT, Some(MAX_REPEAT)));
+                min -= MAX_REPEAT;
+            } else {
+                counts.push((min, Some(MAX_REPEAT)));
+                min = 0;
+            }
+            max = max.map(|max| max - MAX_REPEAT);
+        }
+        counts.push((min, max));
+        if counts.len() > 1 && exprs.iter().any(has_groups) {
+            return Err(OutputError::FeatureNotSupported(
+                "capture groups repeated more than 1000 times",
+            ));
+        }
+
+        let body = self.output_seq(exprs)?;
+        let mut s = String::new();
+        for (min, max) in counts {
+            let quantifier = match max {
+                Some(max) if max == min => format!("{{{min}}}"),
+                Some(max) => format!("{{{min},{max}}}{suffix}"),
+                None => format!("{{{min},}}{suffix}"),
+            };
+            s.push_str(&format!("(?:{body}){quantifier}"));
+        }
+        Ok(s)
+    }
+
+    fn output_atom(&self, atom: &Atom) -> Result<String, OutputError> {
+        match atom {
+            Atom::Char(c) => Ok(self.output_char(*c)),
+            Atom::String(s) => Ok(s.chars().map(|c| self.output_char(c)).collect()),
+            Atom::CharClass(class) => Ok(format!("[{}]", self.output_char_class(class)?)),
+            Atom::NotNewline => Ok("[^\\n]".to_string()),
+            Atom::AnyChar => Ok("(?s:.)".to_string()),
+            Atom::Grapheme => Err(OutputError::FeatureNotSupported("graphemes")),
+            Atom::NewlineSequence => {
+                Ok("(?:\\r\\n|[\\n\\x0B\\f\\r\\x{85}\\x{2028}\\x{2029}])".to_string())
+            }
+        }
+    }
+
+    fn output_char(&self, c: char) -> String {
+        match c {
+            '\\' | '^' | '$' | '.' | '|' | '?' | '*' | '+' | '(' | ')' | '[' | ']' | '{' | '}' => {
+                format!("\\{c}")
+            }
+            '\n' => "\\n".to_string(),
+            '\r' => "\\r".to_string(),
+            '\t' => "\\t".to_string(),
+            _ if c.is_control() => format!("\\x{{{:X}}}", c as u32),
+            _ => c.to_string(),
+        }
+    }
+
+    fn output_set_item(&self, item: &SetItem) -> Result<String, OutputError> {
+        match item {
+            SetItem::Char(c) => self.output_set_char(*c),
+            SetItem::Range(from, to) => self.output_range(*from, *to),
+            SetItem::CharClass(class) => self.output_char_class(class),
+            SetItem::Symbol(_) | SetItem::SymbolRange(_, _) => {
+                Err(OutputError::FeatureNotSupported("unresolved definitions"))
+            }
+        }
+    }
+
+    fn output_range(&self, from: char, to: char) -> Result<String, OutputError> {
+        Ok(format!(
+            "{}-{}",
+            self.output_set_char(from)?,
+            self.output_set_char(to)?
+        ))
+    }
+
+    fn output_set_char(&self, c: char) -> Result<String, OutputError> {
+        match c {
+            // `[` could start a class like `[:alpha:]`.
+            ']' | '[' | '\\' | '^' | '-' => Ok(format!("\\{c}")),
+            '\n' => Ok("\\n".to_string()),
+            '\r' => Ok("\\r".to_string()),
+            '\t' => Ok("\\t".to_string()),
+            _ if c.is_ascii() && !c.is_ascii_control() => Ok(c.to_string()),
+            _ => Ok(format!("\\x{{{:X}}}", c as u32)),
+        }
+    }
+
+    fn output_char_class(&self, class: &CharClass) -> Result<String, OutputError> {
+        match class {
+            CharClass::Whitespace => Ok("\\s".to_string()),
+            CharClass::Alpha => Ok("a-zA-Z".to_string()),
+            CharClass::Digit => Ok("\\d".to_string()),
+            CharClass::AlphaNum => Ok("0-9a-zA-Z".to_string()),
+            CharClass::Hex => Ok("0-9a-fA-F".to_string()),
+            CharClass::LowerCase => Ok("a-z".to_string()),
+            CharClass::UpperCase => Ok("A-Z".to_string()),
+            CharClass::Word => Ok("\\w".to_string()),
+            CharClass::NotWhitespace => Ok("\\S".to_string()),
+            CharClass::NotDigit => Ok("\\D".to_string()),
+            CharClass::NotWord => Ok("\\W".to_string()),
+            CharClass::Category(category) => Ok(format!("\\p{{{category}}}")),
+            CharClass::Script(script) => Ok(format!("\\p{{{script}}}")),
+            CharClass::Block(from, to) => self.output_range(*from, *to),
+        }
+    }
+
+    fn output_assertion(self, assertion: &Assertion) -> Result<String, OutputError> {
+        match assertion {
+            Assertion::LineStart => Ok("^".to_string()),
+            Assertion::LineEnd => Ok("$".to_string()),
+            Assertion::StringStart => Ok("\\A".to_string()),
+            Assertion::StringEnd => Ok("\\z".to_string()),
+            Assertion::StringEndBeforeNewline => Err(OutputError::FeatureNotSupported(
+                "end of string before a newline",
+            )),
+            Assertion::WordBoundary => Ok("\\b".to_string()),
+            Assertion::NotWordBoundary => Ok("\\B".to_string()),
+            Assertion::WordStart | Assertion::WordEnd => {
+                Err(OutputError::FeatureNotSupported("word start and end"))
+            }
+            Assertion::SymbolStart | Assertion::SymbolEnd => {
+                Err(OutputError::FeatureNotSupported("symbol boundaries"))
+            }
+            Assertion::LookAhead(_)
+            | Assertion::NegativeLookAhead(_)
+            | Assertion::LookBehind(_)
+            | Assertion::NegativeLookBehind(_) => {
+                Err(OutputError::FeatureNotSupported("lookarounds"))
+            }
+        }
+    }
+}
+
+/// The characters in a class. Unlike in other flavors, `\s` does
+/// not match a vertical tab.
+fn class_set(class: &CharClass) -> Option<CharSet> {
+    let whitespace = || CharSet::new(vec![(0x09, 0x0A), (0x0C, 0x0D), (0x20, 0x20)]);
+    match class {
+        CharClass::Whitespace => Some(whitespace()),
+        CharClass::NotWhitespace => Some(whitespace().complement()),
+        _ => ascii_class_set(class),
+    }
+}
+
+/// RE2 also limits the product of the counts of nested repetitions,
+/// which splitting a count cannot help with. `enclosing` is the
+/// product of the counts of the repetitions around `expr`.
+fn check_nested_repetitions(expr: &Expr, enclosing: u32) -> Result<(), OutputError> {
+    let count = match expr {
+        Expr::Exactly(n, _) => Some(*n),
+        Expr::AtLeast(n, _) | Expr::AtLeastReluctant(n, _) => Some(*n),
+        Expr::Between(_, m, _) | Expr::BetweenReluctant(_, m, _) => Some(*m),
+        _ => None,
+    };
+    let enclosing = match count {
+        // A count of 0 does not reduce what is left for the others.
+        Some(count) => {
+            let count = count.clamp(1, MAX_REPEAT);
+            if enclosing > 1 && enclosing * count > MAX_REPEAT {
+                return Err(OutputError::FeatureNotSupported(
+                    "nested repetitions of more than 1000 in total",
+                ));
+            }
+            enclosing * count
+        }
+        None => enclosing,
+    };
+    for child in expr.children() {
+        check_nested_repetitions(child, enclosing)?;
+    }
+    Ok(())
+}
+
+fn has_groups(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Group(_) | Expr::GroupN(_, _) | Expr::GroupNamed(_, _)
+    ) || expr.children().iter().any(has_groups)
+}
@@ -0,0 +1,271 @@
+use crate::expr::{Assertion, Atom, CharClass, Expr, Flag, SetItem};
+use crate::output::{check_no_recursion, raw_variant, Output, OutputError};
+
+#[derive(Copy, Clone, Default)]
+pub struct RustRegexOutput {}
+
+impl Output for RustRegexOutput {
+    fn output(&self, expr: &Expr) -> Result<String, OutputError> {
+        check_no_recursion(expr)?;
+        self.output_expr(expr)
+    }
+}
+
+impl RustRegexOutput {
+    fn output_expr(&self, expr: &Expr) -> Result<String, OutputError> {
+        match expr {
+            Expr::Atom(c) => self.output_atom(c),
+            Expr::Seq(exprs) => self.output_seq(exprs),
+            Expr::Or(exprs) if exprs.len() == 1 => self.output_expr(exprs.first().expect("")),
+            Expr::Or(exprs) => {
+                let mut s = self.output_expr(exprs.first().expect(""))?;
+                for e in exprs[1..].iter() {
+                    s.push('|');
+                    s.push_str(&self.output_expr(e)?);
+                }
+                Ok(s)
+            }
+            Expr::ZeroOrOne(exprs) => self.output_quantified(exprs, "?"),
+            Expr::ZeroOrOneReluctant(exprs) => self.output_quantified(exprs, "??"),
+            Expr::ZeroOrMore(exprs) => self.output_quantified(exprs, "*"),
+            Expr::ZeroOrMoreReluctant(exprs) => self.output_quantified(exprs, "*?"),
+            Expr::OneOrMore(exprs) => self.output_quantified(exprs, "+"),
+            Expr::OneOrMoreReluctant(exprs) => self.output_quantified(exprs, "+?"),
+            Expr::Exactly(n, exprs) => self.output_counted(exprs, &format!("{{{n}}}")),
+            Expr::AtLeast(n, exprs) => self.output_counted(exprs, &format!("{{{n},}}")),
+            Expr::AtLeastReluctant(n, exprs) => self.output_counted(exprs, &format!("{{{n},}}?")),
+            Expr::Between(n, m, exprs) => self.output_counted(exprs, &format!("{{{n},{m}}}")),
+            Expr::BetweenReluctant(n, m, exprs) => {
+                self.output_counted(exprs, &format!("{{{n},{m}}}?"))
+            }
+            Expr::ZeroOrOnePossessive(_)
+            | Expr::ZeroOrMorePossessive(_)
+            | Expr::OneOrMorePossessive(_)
+            | Expr::ExactlyPossessive(_, _)
+            | Expr::AtLeastPossessive(_, _)
+            | Expr::BetweenPossessive(_, _, _) => {
+                Err(OutputError::FeatureNotSupported("possessive quantifiers"))
+            }
+            Expr::Atomic(_) => Err(OutputError::FeatureNotSupported("atomic groups")),
+            Expr::Assertion(assertion) => self.output_assertion(assertion),
+            Expr::Intersection(_) | Expr::Difference(_) => self.output_set_operation(expr),
+            Expr::Any(items) => {
+                let mut s = String::from("[");
+                for item in items {
+                    s.push_str(&self.output_set_item(item)?);
+                }
+                s.push(']');
+                Ok(s)
+            }
+            Expr::Not(items) => {
+                let mut s = String::from("[^");
+                for item in items {
+                    s.push_str(&self.output_set_item(item)?);
+                }
+                s.push(']');
+                Ok(s)
+            }
+            Expr::Group(exprs) => Ok(format!("({})", self.output_seq(exprs)?)),
+            Expr::GroupN(n, exprs) => Ok(format!("(?P<n{n}>{})", self.output_seq(exprs)?)),
+            Expr::GroupNamed(name, exprs) => Ok(format!("(?P<{name}>{})", self.output_seq(exprs)?)),
+            Expr::BackRef(_) => Err(OutputError::FeatureNotSupported("backrefs")),
+            Expr::IfGroup(_, _) => Err(OutputError::FeatureNotSupported("conditional groups")),
+            Expr::Recurse | Expr::Call(_) => Err(OutputError::FeatureNotSupported("recursion")),
+            Expr::Raw(variants) => Ok(raw_variant(variants, "rust")?.to_string()),
+            Expr::Reference(_) | Expr::Symbol(_) | Expr::Let(_, _) | Expr::MacroCall(_, _, _) => {
+                Err(OutputError::FeatureNotSupported("unresolved definitions"))
+            }
+            Expr::Scoped(flag, exprs) => {
+                let flag = match flag {
+                    Flag::CaseInsensitive => "i",
+                    Flag::CaseSensitive => "-i",
+                    Flag::Multiline => "m",
+                    Flag::DotAll => "s",
+                };
+                Ok(format!("(?{flag}:{})", self.output_seq(exprs)?))
+            }
+        }
+    }
+
+    fn output_seq(&self, exprs: &[Expr]) -> Result<String, OutputError> {
+        if let [e] = exprs {
+            return self.output_expr(e);
+        }
+        self.output_seq_items(exprs)
+    }
+
+    fn output_seq_items(&self, exprs: &[Expr]) -> Result<String, OutputError> {
+        let mut s = String::new();
+        for e in exprs {
+            // NB In a sequence, we need to avoid mushing
+            // together several tokens that will then get
+            // affected by a postfix modifier.
+            match e {
+                Expr::Atom(_) => s.push_str(&self.output_expr(e)?),
+                Expr::Assertion(_) => s.push_str(&self.output_expr(e)?),
+                Expr::Any(_) | Expr::Not(_) | Expr::Intersection(_) | Expr::Difference(_) => {
+                    s.push_str(&self.output_expr(e)?)
+                }
+                Expr::Seq(exprs) => s.push_str(&self.output_seq_items(exprs)?),
+                _ => s.push_str(&format!("(?:{})", self.output_expr(e)?)),
+            }
+        }
+        Ok(s)
+    }
+
+    /// Set operations are supported natively, with nested sets as
+    /// operands.
+    fn output_set_operation(&self, expr: &Expr) -> Result<String, OutputError> {
+        let (operator, operands) = match expr {
+            Expr::Intersection(operands) => ("&&", operands),
+            Expr::Difference(operands) => ("--", operands),
+            _ => return self.output_expr(expr),
+        };
+        let mut s = String::from("[");
+        for (idx, operand) in operands.iter().enumerate() {
+            if idx > 0 {
+                s.push_str(operator);
+            }
+            s.push_str(&self.output_set_operand(operand)?);
+        }
+        s.push(']');
+        Ok(s)
+    }
+
+    fn output_set_operand(&self, expr: &Expr) -> Result<String, OutputError> {
+        match expr {
+            Expr::Any(_)
+            | Expr::Not(_)
+            | Expr::Intersection(_)
+            | Expr::Difference(_)
+            | Expr::Atom(Atom::CharClass(_) | Atom::NotNewline) => self.output_expr(expr),
+            Expr::Atom(Atom::Char(c)) => Ok(format!("[{}]", self.output_set_char(*c)?)),
+            Expr::Atom(Atom::String(s)) if s.chars().count() == 1 => Ok(format!(
+                "[{}]",
+                self.output_set_char(s.chars().next().expect("one char"))?
+            )),
+            Expr::Atom(Atom::AnyChar) => Ok("[\\s\\S]".to_string()),
+            _ => Err(OutputError::NotACharSet),
+        }
+    }
+
+    fn output_quantified(&self, exprs: &[Expr], quantifier: &str) -> Result<String, OutputError> {
+        match exprs {
+            // Single characters can take a quantifier directly, but
+            // anything longer needs to be grouped.
+            [Expr::Atom(Atom::String(s))] if s.chars().count() != 1 => {
+                Ok(format!("(?:{}){quantifier}", self.output_seq(exprs)?))
+            }
+            [Expr::Atom(atom)] => Ok(format!("{}{quantifier}", self.output_atom(atom)?)),
+            _ => Ok(format!("(?:{}){quantifier}", self.output_seq(exprs)?)),
+        }
+    }
+
+    fn output_counted(&self, exprs: &[Expr], quantifier: &str) -> Result<String, OutputError> {
+        Ok(format!("(?:{}){quantifier}", self.output_seq(exprs)?))
+    }
+
+    fn output_atom(&self, atom: &Atom) -> Result<String, OutputError> {
+        match atom {
+            Atom::Char(c) => Ok(self.output_char(*c)),
+            Atom::String(s) => Ok(s.chars().map(|c| self.output_char(c)).collect()),
+            Atom::CharClass(class) => Ok(format!("[{}]", self.output_char_class(class)?)),
+            // `.` also excludes `\r` in CRLF mode.
+            Atom::NotNewline => Ok("[^\\n]".to_string()),
+            Atom::AnyChar => Ok("(?s:.)".to_string()),
+            Atom::Grapheme => Err(OutputError::FeatureNotSupported("graphemes")),
+            Atom::NewlineSequence => {
+                Ok("(?:\\r\\n|[\\n\\x0B\\x0C\\r\\x{85}\\x{2028}\\x{2029}])".to_string())
+            }
+        }
+    }
+
+    fn output_char(&self, c: char) -> String {
+        match c {
+            '\\' | '^' | '$' | '.' | '|' | '?' | '*' | '+' | '(' | ')' | '[' | ']' | '{' | '}' => {
+                format!("\\{c}")
+            }
+            '\n' => "\\n".to_string(),
+            '\r' => "\\r".to_string(),
+            '\t' => "\\t".to_string(),
+            _ if c.is_control() => format!("\\x{{{:X}}}", c as u32),
+            _ => c.to_string(),
+        }
+    }
+
+    fn output_set_item(&self, item: &SetItem) -> Result<String, OutputError> {
+        match item {
+            SetItem::Char(c) => self.output_set_char(*c),
+            SetItem::Range(from, to) => self.output_range(*from, *to),
+            SetItem::CharClass(class) => self.output_char_class(class),
+            SetItem::Symbol(_) | SetItem::SymbolRange(_, _) => {
+                Err(OutputError::FeatureNotSupported("unresolved definitions"))
+            }
+        }
+    }
+
+    fn output_range(&self, from: char, to: char) -> Result<String, OutputError> {
+        Ok(format!(
+            "{}-{}",
+            self.output_set_char(from)?,
+            self.output_set_char(to)?
+        ))
+    }
+
+    fn output_set_char(&self, c: char) -> Result<String, OutputError> {
+        match c {
+            // `[` starts a nested set, and `&&`, `--` and `~~` are
+            // set operators.
+            ']' | '[' | '\\' | '^' | '-' | '&' | '~' => Ok(format!("\\{c}")),
+            '\n' => Ok("\\n".to_string()),
+            '\r' => Ok("\\r".to_string()),
+            '\t' => Ok("\\t".to_string()),
+            _ if c.is_ascii() && !c.is_ascii_control() => Ok(c.to_string()),
+            _ => Ok(format!("\\x{{{:X}}}", c as u32)),
+        }
+    }
+
+    fn output_char_class(&self, class: &CharClass) -> Result<String, OutputError> {
+        match class {
+            CharClass::Whitespace => Ok("\\s".to_string()),
+            CharClass::Alpha => Ok("a-zA-Z".to_string()),
+            CharClass::Digit => Ok("\\d".to_string()),
+            CharClass::AlphaNum => Ok("0-9a-zA-Z".to_string()),
+            CharClass::Hex => Ok("0-9a-fA-F".to_string()),
+            CharClass::LowerCase => Ok("a-z".to_string()),
+            CharClass::UpperCase => Ok("A-Z".to_string()),
+            CharClass::Word => Ok("\\w".to_string()),
+            CharClass::NotWhitespace => Ok("\\S".to_string()),
+            CharClass::NotDigit => Ok("\\D".to_string()),
+            CharClass::NotWord => Ok("\\W".to_string()),
+            CharClass::Category(category) => Ok(format!("\\p{{{category}}}")),
+            CharClass::Script(script) => Ok(format!("\\p{{{script}}}")),
+            CharClass::Block(from, to) => self.output_range(*from, *to),
+        }
+    }
+
+    fn output_assertion(self, assertion: &Assertion) -> Result<String, OutputError> {
+        match assertion {
+            Assertion::LineStart => Ok("^".to_string()),
+            Assertion::LineEnd => Ok("$".to_string()),
+            Assertion::StringStart => Ok("\\A".to_string()),
+            Assertion::StringEnd => Ok("\\z".to_string()),
+            Assertion::StringEndBeforeNewline => Err(OutputError::FeatureNotSupported(
+                "end of string before a newline",
+            )),
+            Assertion::WordBoundary => Ok("\\b".to_string()),
+            Assertion::NotWordBoundary => Ok("\\B".to_string()),
+            Assertion::WordStart => Ok("\\b{start}".to_string()),
+            Assertion::WordEnd => Ok("\\b{end}".to_string()),
+            Assertion::SymbolStart | Assertion::SymbolEnd => {
+                Err(OutputError::FeatureNotSupported("symbol boundaries"))
+            }
+            Assertion::LookAhead(_)
+            | Assertion::NegativeLookAhead(_)
+            | Assertion::LookBehind(_)
+            | Assertion::NegativeLookBehind(_) => {
+                Err(OutputError::FeatureNotSupported("lookarounds"))
+            }
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use crate::expr::{Assertion, Atom, CharClass, Expr, Flag, SetItem};
+use crate::output::OutputError;
+
+/// All code points which are characters, i.e. not surrogates.
+const ALL: [(u32, u32); 2] = [(0, 0xD7FF), (0xE000, 0x10FFFF)];
+
+/// A set of characters, as sorted ranges of code points which neither
+/// overlap nor touch.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct CharSet(Vec<(u32, u32)>);
+
+impl CharSet {
+    pub(crate) fn new(mut ranges: Vec<(u32, u32)>) -> Self {
+        ranges.sort_unstable();
+        let mut merged: Vec<(u32, u32)> = vec![];
+        for (from, to) in ranges {
+            match merged.last_mut() {
+                Some((_, last)) if from <= last.saturating_add(1) => *last = (*last).max(to),
+                _ => merged.push((from, to)),
+            }
+        }
+        CharSet(merged)
+    }
+
+    fn range(from: char, to: char) -> Self {
+        CharSet(vec![(from as u32, to as u32)])
+    }
+
+    fn chars(chars: &[char]) -> Self {
+        CharSet::new(chars.iter().map(|c| (*c as u32, *c as u32)).collect())
+    }
+
+    fn union(&self, other: &CharSet) -> Self {
+        CharSet::new(self.0.iter().chain(&other.0).copied().collect())
+    }
+
+    fn intersection(&self, other: &CharSet) -> Self {
+        let mut ranges = vec![];
+        for (from_a, to_a) in &self.0 {
+            for (from_b, to_b) in &other.0 {
+                let (from, to) = (*from_a.max(from_b), *to_a.min(to_b));
+                if from <= to {
+                    ranges.push((from, to));
+                }
+            }
+        }
+        CharSet::new(ranges)
+    }
+
+    pub(crate) fn complement(&self) -> Self {
+        let mut ranges = vec![];
+        for (from, to) in ALL {
+            let mut next = from;
+            for (excluded_from, excluded_to) in &self.0 {
+                if *excluded_to < next || *excluded_from > to {
+                    continue;
+                }
+                if *excluded_from > next {
+                    ranges.push((next, excluded_from - 1));
+                }
+                next = excluded_to + 1;
+            }
+            if next <= to {
+                ranges.push((next, to));
+            }
+        }
+        CharSet::new(ranges)
+    }
+
+    fn difference(&self, other: &CharSet) -> Self {
+        self.intersection(&other.complement())
+    }
+
+    fn items(&self) -> Vec<SetItem> {
+        let char = |c| char::from_u32(c).expect("surrogates are never in a set");
+        self.0
+            .iter()
+            .flat_map(|(from, to)| match to - from {
+                0 => vec![SetItem::Char(char(*from))],
+                1 => vec![SetItem::Char(char(*from)), SetItem::Char(char(*to))],
+                _ => vec![SetItem::Range(char(*from), char(*to))],
+            })
+            .collect()
+    }
+
+    /// An expression matching any character in the set.
+    pub(crate) fn to_expr(&self) -> Expr {
+        let complement = self.complement();
+        if self.0.is_empty() {
+            // Never matches.
+            Expr::Assertion(Assertion::NegativeLookAhead(vec![]))
+        } else if complement.0.is_empty() {
+            Expr::Atom(Atom::AnyChar)
+        } else if self.0.last().map(|(_, to)| *to) == Some(0x10FFFF) {
+            // Sets with the highest code point are mostly what is
+            // left from excluding a few characters.
+            Expr::Not(complement.items())
+        } else {
+            Expr::Any(self.items())
+        }
+    }
+}
+
+/// The characters in a class, as ASCII-only regex engines see them,
+/// or `None` for Unicode properties.
+pub(crate) fn ascii_class_set(class: &CharClass) -> Option<CharSet> {
+    let set = match class {
+        CharClass::Whitespace => CharSet::new(vec![(0x09, 0x0D), (0x20, 0x20)]),
+        CharClass::Alpha => CharSet::range('a', 'z').union(&CharSet::range('A', 'Z')),
+        CharClass::Digit => CharSet::range('0', '9'),
+        CharClass::AlphaNum => ascii_class_set(&CharClass::Alpha)?.union(&CharSet::range('0', '9')),
+        CharClass::Hex => CharSet::new(vec![
+            ('0' as u32, '9' as u32),
+            ('A' as u32, 'F' as u32),
+            ('a' as u32, 'f' as u32),
+        ]),
+        CharClass::LowerCase => CharSet::range('a', 'z'),
+        CharClass::UpperCase => CharSet::range('A', 'Z'),
+        CharClass::Word => ascii_class_set(&CharClass::AlphaNum)?.union(&CharSet::chars(&['_'])),
+        CharClass::NotWhitespace => ascii_class_set(&CharClass::Whitespace)?.complement(),
+        CharClass::NotDigit => ascii_class_set(&CharClass::Digit)?.complement(),
+        CharClass::NotWord => ascii_class_set(&CharClass::Word)?.complement(),
+        CharClass::Block(from, to) => CharSet::range(*from, *to),
+        CharClass::Category(_) | CharClass::Script(_) => return None,
+    };
+    Some(set)
+}
+
+/// Computes the characters matched by a character set expression,
+/// using `class_set` for the classes in it. This is `None` if the
+/// classes are not all known.
+pub(crate) fn eval_set(
+    expr: &Expr,
+    class_set: &dyn Fn(&CharClass) -> Option<CharSet>,
+) -> Result<Option<CharSet>, OutputError> {
+    let items_set = |items: &[SetItem]| {
+        items.iter().try_fold(CharSet(vec![]), |set, item| {
+            let item_set = match item {
+                SetItem::Char(c) => CharSet::chars(&[*c]),
+                SetItem::Range(from, to) => CharSet::range(*from, *to),
+                SetItem::CharClass(class) => class_set(class)?,
+                // Symbols are resolved before output.
+                SetItem::Symbol(_) | SetItem::SymbolRange(_, _) => return None,
+            };
+            Some(set.union(&item_set))
+        })
+    };
+    let set = match expr {
+        Expr::Any(items) => items_set(items),
+        Expr::Not(items) => items_set(items).map(|set| set.complement()),
+        Expr::Atom(Atom::Char(c)) => Some(CharSet::chars(&[*c])),
+        Expr::Atom(Atom::String(s)) if s.chars().count() == 1 => {
+            Some(CharSet::chars(&s.chars().collect::<Vec<_>>()))
+        }
+        Expr::Atom(Atom::CharClass(class)) => class_set(class),
+        Expr::Atom(Atom::NotNewline) => Some(CharSet::chars(&['\n']).complement()),
+        Expr::Atom(Atom::AnyChar) => Some(CharSet(ALL.to_vec())),
+        Expr::Intersection(operands) | Expr::Difference(operands) => {
+            let mut sets = vec![];
+            for operand in operands {
+                sets.push(eval_set(operand, class_set)?);
+            }
+            let sets: Option<Vec<CharSet>> = sets.into_iter().collect();
+            sets.and_then(|sets| {
+                let (first, rest) = sets.split_first()?;
+                Some(rest.iter().fold(first.clone(), |set, other| match expr {
+                    Expr::Intersection(_) => set.intersection(other),
+                    _ => set.difference(other),
+                }))
+            })
+        }
+        _ => return Err(OutputError::NotACharSet),
+    };
+    Ok(set)
+}
+
+/// An expression equivalent to a set operation, made of lookaheads,
+/// for when the resulting set cannot be computed.
+pub(crate) fn set_operation_lookarounds(expr: &Expr) -> Vec<Expr> {
+    match expr {
+        Expr::Intersection(operands) => {
+            let (last, rest) = operands.split_last().expect("operands");
+            rest.iter()
+                .map(|e| Expr::Assertion(Assertion::LookAhead(vec![e.clone()])))
+                .chain([last.clone()])
+                .collect()
+        }
+        Expr::Difference(operands) => {
+            let (first, rest) = operands.split_first().expect("operands");
+            rest.iter()
+                .map(|e| Expr::Assertion(Assertion::NegativeLookAhead(vec![e.clone()])))
+                .chain([first.clone()])
+                .collect()
+        }
+        _ => vec![expr.clone()],
+    }
+}
+
+/// Replaces set operations in case-insensitive scopes with lookaheads,
+/// as computing the set would ignore that each operand matches
+/// regardless of case.
+pub(crate) fn case_insensitive_set_operations(expr: &mut Expr, case_insensitive: bool) {
+    let case_insensitive = match expr {
+        Expr::Scoped(Flag::CaseInsensitive, _) => true,
+        Expr::Scoped(Flag::CaseSensitive, _) => false,
+        _ => case_insensitive,
+    };
+    if case_insensitive && matches!(expr, Expr::Intersection(_) | Expr::Difference(_)) {
+        *expr = Expr::Seq(set_operation_lookarounds(expr));
+    }
+    for child in expr.children_mut() {
+        case_insensitive_set_operations(child, case_insensitive);
+    }
+}
+
+/// Whether there are set operations in case-insensitive scopes, for
+/// output formats which cannot replace them with lookaheads.
+pub(crate) fn has_case_insensitive_set_operations(expr: &Expr, case_insensitive: bool) -> bool {
+    let case_insensitive = match expr {
+        Expr::Scoped(Flag::CaseInsensitive, _) => true,
+        Expr::Scoped(Flag::CaseSensitive, _) => false,
+        _ => case_insensitive,
+    };
+    (case_insensitive && matches!(expr, Expr::Intersection(_) | Expr::Difference(_)))
+        || expr
+            .children()
+            .iter()
+            .any(|child| has_case_insensitive_set_operations(child, case_insensitive))
+}
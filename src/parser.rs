@@ -1,36 +1,111 @@
 use nom::branch::alt;
-use nom::bytes::complete::{escaped, tag};
-use nom::character::complete::{anychar, char, digit1, multispace0, multispace1, none_of, one_of};
-use nom::combinator::{eof, map};
-use nom::multi::many1;
-use nom::sequence::{delimited, preceded, terminated, tuple};
+use nom::bytes::complete::{
+    escaped_transform, tag, take_till, take_until, take_while1, take_while_m_n,
+};
+use nom::character::complete::{anychar, char, digit1, multispace1, none_of, satisfy};
+use nom::combinator::{
+    all_consuming, consumed, eof, map, map_opt, map_res, not, opt, peek, recognize, value, verify,
+};
+use nom::multi::{many0, many0_count, many1, many1_count, separated_list1};
+use nom::sequence::{delimited, preceded, separated_pair, terminated, tuple};
 use nom::IResult;
 
-use crate::expr::{Assertion, Atom, CharClass, Expr};
+use crate::expr::{
+    Assertion, Atom, CharClass, Definition, Expr, Flag, GroupRef, RangeEnd, SetItem,
+};
 
 pub fn parse(i: &str) -> IResult<&str, Expr> {
-    terminated(parse_expr, eof)(i)
+    map(
+        terminated(
+            preceded(
+                ws0,
+                tuple((many0(terminated(parse_define, ws1)), parse_expr)),
+            ),
+            tuple((ws0, eof)),
+        ),
+        |(definitions, expr)| {
+            if definitions.is_empty() {
+                expr
+            } else {
+                Expr::Let(definitions, Box::new(expr))
+            }
+        },
+    )(i)
+}
+
+/// Optional whitespace and comments.
+fn ws0(i: &str) -> IResult<&str, &str> {
+    recognize(many0_count(alt((multispace1, line_comment, block_comment))))(i)
+}
+
+/// Whitespace and comments, at least one of either.
+fn ws1(i: &str) -> IResult<&str, &str> {
+    recognize(many1_count(alt((multispace1, line_comment, block_comment))))(i)
+}
+
+/// A comment from `;` to the end of the line.
+fn line_comment(i: &str) -> IResult<&str, &str> {
+    recognize(tuple((char(';'), take_till(|c| c == '\n'))))(i)
+}
+
+/// A comment between `#|` and `|#`.
+fn block_comment(i: &str) -> IResult<&str, &str> {
+    recognize(tuple((tag("#|"), take_until("|#"), tag("|#"))))(i)
+}
+
+/// A keyword, which must not be directly followed by more symbol
+/// characters, so that e.g. `digits` does not parse as `digit`.
+fn keyword<'a>(kw: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    terminated(tag(kw), not(peek(satisfy(is_symbol_char))))
+}
+
+fn is_symbol_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
 }
 
 fn parse_expr(i: &str) -> IResult<&str, Expr> {
     alt((
         parse_seq,
         parse_or,
+        parse_quantified,
+        parse_not,
+        parse_any,
+        parse_set_operation,
+        parse_assertion,
+        parse_lookaround,
+        parse_atomic,
+        parse_group,
+        parse_group_n,
+        parse_group_named,
+        parse_backref,
+        parse_if_group,
+        parse_recursion,
+        parse_scoped,
+        parse_let,
+        parse_regexp,
+        parse_atom_expr,
+    ))(i)
+}
+
+fn parse_quantified(i: &str) -> IResult<&str, Expr> {
+    alt((
         parse_zero_or_one,
+        parse_zero_or_one_reluctant,
+        parse_zero_or_one_possessive,
         parse_zero_or_more,
         parse_zero_or_more_reluctant,
+        parse_zero_or_more_possessive,
         parse_one_or_more,
         parse_one_or_more_reluctant,
+        parse_one_or_more_possessive,
         parse_exactly,
+        parse_exactly_possessive,
         parse_at_least,
+        parse_at_least_reluctant,
+        parse_at_least_possessive,
         parse_between,
-        parse_not,
-        parse_any,
-        parse_assertion,
-        parse_group,
-        parse_group_n,
-        parse_backref,
-        parse_atom_expr,
+        parse_between_reluctant,
+        parse_between_possessive,
     ))(i)
 }
 
@@ -39,11 +114,16 @@ fn parse_seq(i: &str) -> IResult<&str, Expr> {
         delimited(
             tuple((
                 char('('),
-                multispace0,
-                alt((tag("seq"), tag(":"), tag("sequence"), tag("and"))),
+                ws0,
+                alt((
+                    keyword("seq"),
+                    keyword(":"),
+                    keyword("sequence"),
+                    keyword("and"),
+                )),
             )),
-            many1(preceded(multispace1, parse_expr)),
-            tuple((multispace0, char(')'))),
+            many1(preceded(ws1, parse_expr)),
+            tuple((ws0, char(')'))),
         ),
         Expr::Seq,
     )(i)
@@ -52,50 +132,163 @@ fn parse_seq(i: &str) -> IResult<&str, Expr> {
 fn parse_or(i: &str) -> IResult<&str, Expr> {
     map(
         delimited(
-            tuple((char('('), multispace0, alt((tag("or"), tag("|"))))),
-            many1(preceded(multispace1, parse_expr)),
-            tuple((multispace0, char(')'))),
+            tuple((char('('), ws0, alt((keyword("or"), keyword("|"))))),
+            many1(preceded(ws1, parse_expr)),
+            tuple((ws0, char(')'))),
         ),
         Expr::Or,
     )(i)
 }
 
 fn parse_any(i: &str) -> IResult<&str, Expr> {
+    map(parse_any_set, Expr::Any)(i)
+}
+
+fn parse_any_set(i: &str) -> IResult<&str, Vec<SetItem>> {
     map(
         delimited(
             tuple((
                 char('('),
-                multispace0,
-                alt((tag("any"), tag("in"), tag("char"))),
+                ws0,
+                alt((keyword("any"), keyword("in"), keyword("char"))),
+            )),
+            many1(preceded(ws1, parse_set_items)),
+            tuple((ws0, char(')'))),
+        ),
+        |items| items.into_iter().flatten().collect(),
+    )(i)
+}
+
+/// An `or` of single characters and classes, which can be treated
+/// as a set.
+fn parse_or_set(i: &str) -> IResult<&str, Vec<SetItem>> {
+    map(
+        delimited(
+            tuple((char('('), ws0, alt((keyword("or"), keyword("|"))))),
+            many1(preceded(
+                ws1,
+                alt((
+                    parse_any_set,
+                    parse_or_set,
+                    map(parse_range, |r| vec![r]),
+                    map(parse_char_class, |c| vec![SetItem::CharClass(c)]),
+                    map(
+                        verify(parse_string_literal, |s: &str| s.chars().count() == 1),
+                        |s| s.chars().map(SetItem::Char).collect(),
+                    ),
+                    map(parse_symbol, |c| vec![SetItem::Symbol(c)]),
+                    map(
+                        alt((parse_char_literal, parse_char_code, parse_bare_char)),
+                        |c| vec![SetItem::Char(c)],
+                    ),
+                )),
             )),
-            many1(preceded(multispace1, parse_atom)),
-            tuple((multispace0, char(')'))),
+            tuple((ws0, char(')'))),
         ),
-        Expr::Any,
+        |items| items.into_iter().flatten().collect(),
     )(i)
 }
 
+fn parse_set_items(i: &str) -> IResult<&str, Vec<SetItem>> {
+    alt((
+        map(parse_range, |r| vec![r]),
+        map(parse_char_class, |c| vec![SetItem::CharClass(c)]),
+        map_opt(parse_string_literal, |s| string_set_items(&s)),
+        map(parse_symbol, |c| vec![SetItem::Symbol(c)]),
+        map(
+            alt((parse_char_literal, parse_char_code, parse_bare_char)),
+            |c| vec![SetItem::Char(c)],
+        ),
+    ))(i)
+}
+
+/// A range of characters. Ends given as symbols are only known once
+/// they are resolved, and so are their order.
+fn parse_range(i: &str) -> IResult<&str, SetItem> {
+    map_opt(
+        delimited(
+            tuple((char('('), ws0, keyword("range"), ws1)),
+            separated_pair(parse_range_end, ws1, parse_range_end),
+            tuple((ws0, char(')'))),
+        ),
+        |(from, to)| match (from, to) {
+            (RangeEnd::Char(from), RangeEnd::Char(to)) => {
+                (from <= to).then_some(SetItem::Range(from, to))
+            }
+            (from, to) => Some(SetItem::SymbolRange(from, to)),
+        },
+    )(i)
+}
+
+fn parse_range_end(i: &str) -> IResult<&str, RangeEnd> {
+    alt((
+        map(parse_symbol, RangeEnd::Symbol),
+        map(
+            alt((parse_char_literal, parse_char_code, parse_bare_char)),
+            RangeEnd::Char,
+        ),
+    ))(i)
+}
+
+/// Splits a string in a set into its characters, where `a-z` denotes
+/// a range, like in Emacs. A `-` at either end is taken literally.
+fn string_set_items(s: &str) -> Option<Vec<SetItem>> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut items = vec![];
+    let mut idx = 0;
+    while idx < chars.len() {
+        if idx + 2 < chars.len() && chars[idx + 1] == '-' {
+            if chars[idx] > chars[idx + 2] {
+                return None;
+            }
+            items.push(SetItem::Range(chars[idx], chars[idx + 2]));
+            idx += 3;
+        } else {
+            items.push(SetItem::Char(chars[idx]));
+            idx += 1;
+        }
+    }
+    Some(items)
+}
+
 fn parse_not(i: &str) -> IResult<&str, Expr> {
     map(
         delimited(
-            tuple((char('('), multispace0, tag("not"), multispace1)),
-            parse_atom,
-            tuple((multispace0, char(')'))),
+            tuple((char('('), ws0, keyword("not"), ws1)),
+            alt((parse_any_set, parse_or_set, parse_set_items)),
+            tuple((ws0, char(')'))),
         ),
         Expr::Not,
     )(i)
 }
 
+fn parse_set_operation(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((char('('), ws0)),
+            tuple((
+                alt((keyword("intersection"), keyword("difference"))),
+                many1(preceded(ws1, parse_expr)),
+            )),
+            tuple((ws0, char(')'))),
+        ),
+        |(op, exprs)| match op {
+            "intersection" => Expr::Intersection(exprs),
+            _ => Expr::Difference(exprs),
+        },
+    )(i)
+}
+
 fn parse_zero_or_one(i: &str) -> IResult<&str, Expr> {
     map(
         delimited(
             tuple((
                 char('('),
-                multispace0,
-                alt((tag("zero-or-one"), tag("opt"), tag("optional"))),
+                ws0,
+                alt((keyword("zero-or-one"), keyword("opt"), keyword("optional"))),
             )),
-            many1(preceded(multispace1, parse_expr)),
-            tuple((multispace0, char(')'))),
+            many1(preceded(ws1, parse_expr)),
+            tuple((ws0, char(')'))),
         ),
         Expr::ZeroOrOne,
     )(i)
@@ -106,11 +299,11 @@ fn parse_zero_or_more(i: &str) -> IResult<&str, Expr> {
         delimited(
             tuple((
                 char('('),
-                multispace0,
-                alt((tag("zero-or-more"), tag("0+"), tag("*"))),
+                ws0,
+                alt((keyword("zero-or-more"), keyword("0+"), keyword("*"))),
             )),
-            many1(preceded(multispace1, parse_expr)),
-            tuple((multispace0, char(')'))),
+            many1(preceded(ws1, parse_expr)),
+            tuple((ws0, char(')'))),
         ),
         Expr::ZeroOrMore,
     )(i)
@@ -119,9 +312,9 @@ fn parse_zero_or_more(i: &str) -> IResult<&str, Expr> {
 fn parse_zero_or_more_reluctant(i: &str) -> IResult<&str, Expr> {
     map(
         delimited(
-            tuple((char('('), multispace0, tag("*?"))),
-            many1(preceded(multispace1, parse_expr)),
-            tuple((multispace0, char(')'))),
+            tuple((char('('), ws0, keyword("*?"))),
+            many1(preceded(ws1, parse_expr)),
+            tuple((ws0, char(')'))),
         ),
         Expr::ZeroOrMoreReluctant,
     )(i)
@@ -132,11 +325,11 @@ fn parse_one_or_more(i: &str) -> IResult<&str, Expr> {
         delimited(
             tuple((
                 char('('),
-                multispace0,
-                alt((tag("one-or-more"), tag("1+"), tag("+"))),
+                ws0,
+                alt((keyword("one-or-more"), keyword("1+"), keyword("+"))),
             )),
-            many1(preceded(multispace1, parse_expr)),
-            tuple((multispace0, char(')'))),
+            many1(preceded(ws1, parse_expr)),
+            tuple((ws0, char(')'))),
         ),
         Expr::OneOrMore,
     )(i)
@@ -145,9 +338,9 @@ fn parse_one_or_more(i: &str) -> IResult<&str, Expr> {
 fn parse_one_or_more_reluctant(i: &str) -> IResult<&str, Expr> {
     map(
         delimited(
-            tuple((char('('), multispace0)),
-            preceded(tag("+?"), many1(preceded(multispace1, parse_expr))),
-            tuple((multispace0, char(')'))),
+            tuple((char('('), ws0)),
+            preceded(keyword("+?"), many1(preceded(ws1, parse_expr))),
+            tuple((ws0, char(')'))),
         ),
         Expr::OneOrMoreReluctant,
     )(i)
@@ -156,62 +349,179 @@ fn parse_one_or_more_reluctant(i: &str) -> IResult<&str, Expr> {
 fn parse_exactly(i: &str) -> IResult<&str, Expr> {
     map(
         delimited(
-            tuple((char('('), multispace0, tag("="), multispace1)),
-            tuple((digit1, many1(preceded(multispace1, parse_expr)))),
-            tuple((multispace0, char(')'))),
+            tuple((char('('), ws0, keyword("="), ws1)),
+            tuple((
+                map_res(digit1, str::parse),
+                many1(preceded(ws1, parse_expr)),
+            )),
+            tuple((ws0, char(')'))),
         ),
-        |(n, exprs)| {
-            Expr::Exactly(
-                n.parse().expect("failed to parse exactly quantifier"),
-                exprs,
-            )
-        },
+        |(n, exprs)| Expr::Exactly(n, exprs),
     )(i)
 }
 
 fn parse_at_least(i: &str) -> IResult<&str, Expr> {
     map(
         delimited(
-            tuple((char('('), multispace0, tag(">="), multispace1)),
-            tuple((digit1, many1(preceded(multispace1, parse_expr)))),
-            tuple((multispace0, char(')'))),
+            tuple((char('('), ws0, keyword(">="), ws1)),
+            tuple((
+                map_res(digit1, str::parse),
+                many1(preceded(ws1, parse_expr)),
+            )),
+            tuple((ws0, char(')'))),
         ),
-        |(n, exprs)| {
-            Expr::AtLeast(
-                n.parse().expect("failed to parse exactly quantifier"),
-                exprs,
-            )
-        },
+        |(n, exprs)| Expr::AtLeast(n, exprs),
     )(i)
 }
 
 fn parse_between(i: &str) -> IResult<&str, Expr> {
     map(
         delimited(
-            tuple((char('('), multispace0, tag("**"), multispace1)),
+            tuple((char('('), ws0, keyword("**"), ws1)),
+            tuple((parse_count_range, many1(preceded(ws1, parse_expr)))),
+            tuple((ws0, char(')'))),
+        ),
+        |((n, m), exprs)| Expr::Between(n, m, exprs),
+    )(i)
+}
+
+/// The counts of a bounded repetition, which must not be reversed.
+fn parse_count_range(i: &str) -> IResult<&str, (u32, u32)> {
+    verify(
+        separated_pair(
+            map_res(digit1, str::parse),
+            ws1,
+            map_res(digit1, str::parse),
+        ),
+        |(n, m)| n <= m,
+    )(i)
+}
+
+fn parse_zero_or_one_reluctant(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((char('('), ws0, alt((keyword("opt?"), keyword("??"))))),
+            many1(preceded(ws1, parse_expr)),
+            tuple((ws0, char(')'))),
+        ),
+        Expr::ZeroOrOneReluctant,
+    )(i)
+}
+
+fn parse_at_least_reluctant(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword(">=?"), ws1)),
             tuple((
-                digit1,
-                preceded(multispace1, digit1),
-                many1(preceded(multispace1, parse_expr)),
+                map_res(digit1, str::parse),
+                many1(preceded(ws1, parse_expr)),
             )),
-            tuple((multispace0, char(')'))),
-        ),
-        |(n, m, exprs)| {
-            Expr::Between(
-                n.parse().expect("failed to parse exactly quantifier"),
-                m.parse().expect("failed to parse exactly quantifier"),
-                exprs,
-            )
-        },
+            tuple((ws0, char(')'))),
+        ),
+        |(n, exprs)| Expr::AtLeastReluctant(n, exprs),
+    )(i)
+}
+
+fn parse_between_reluctant(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword("**?"), ws1)),
+            tuple((parse_count_range, many1(preceded(ws1, parse_expr)))),
+            tuple((ws0, char(')'))),
+        ),
+        |((n, m), exprs)| Expr::BetweenReluctant(n, m, exprs),
+    )(i)
+}
+
+fn parse_zero_or_one_possessive(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((char('('), ws0, alt((keyword("opt+"), keyword("?+"))))),
+            many1(preceded(ws1, parse_expr)),
+            tuple((ws0, char(')'))),
+        ),
+        Expr::ZeroOrOnePossessive,
+    )(i)
+}
+
+fn parse_zero_or_more_possessive(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword("*+"))),
+            many1(preceded(ws1, parse_expr)),
+            tuple((ws0, char(')'))),
+        ),
+        Expr::ZeroOrMorePossessive,
+    )(i)
+}
+
+fn parse_one_or_more_possessive(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword("++"))),
+            many1(preceded(ws1, parse_expr)),
+            tuple((ws0, char(')'))),
+        ),
+        Expr::OneOrMorePossessive,
+    )(i)
+}
+
+fn parse_exactly_possessive(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword("=+"), ws1)),
+            tuple((
+                map_res(digit1, str::parse),
+                many1(preceded(ws1, parse_expr)),
+            )),
+            tuple((ws0, char(')'))),
+        ),
+        |(n, exprs)| Expr::ExactlyPossessive(n, exprs),
+    )(i)
+}
+
+fn parse_at_least_possessive(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword(">=+"), ws1)),
+            tuple((
+                map_res(digit1, str::parse),
+                many1(preceded(ws1, parse_expr)),
+            )),
+            tuple((ws0, char(')'))),
+        ),
+        |(n, exprs)| Expr::AtLeastPossessive(n, exprs),
+    )(i)
+}
+
+fn parse_between_possessive(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword("**+"), ws1)),
+            tuple((parse_count_range, many1(preceded(ws1, parse_expr)))),
+            tuple((ws0, char(')'))),
+        ),
+        |((n, m), exprs)| Expr::BetweenPossessive(n, m, exprs),
+    )(i)
+}
+
+fn parse_atomic(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword("atomic"))),
+            many1(preceded(ws1, parse_expr)),
+            tuple((ws0, char(')'))),
+        ),
+        Expr::Atomic,
     )(i)
 }
 
 fn parse_group(i: &str) -> IResult<&str, Expr> {
     map(
         delimited(
-            tuple((char('('), multispace0, alt((tag("group"), tag("submatch"))))),
-            many1(preceded(multispace1, parse_expr)),
-            tuple((multispace0, char(')'))),
+            tuple((char('('), ws0, alt((keyword("group"), keyword("submatch"))))),
+            many1(preceded(ws1, parse_expr)),
+            tuple((ws0, char(')'))),
         ),
         Expr::Group,
     )(i)
@@ -222,76 +532,606 @@ fn parse_group_n(i: &str) -> IResult<&str, Expr> {
         delimited(
             tuple((
                 char('('),
-                multispace0,
-                alt((tag("group-n"), tag("submatch-n"))),
-                multispace1,
+                ws0,
+                alt((keyword("group-n"), keyword("submatch-n"))),
+                ws1,
+            )),
+            tuple((
+                map_res(digit1, str::parse),
+                many1(preceded(ws1, parse_expr)),
+            )),
+            tuple((ws0, char(')'))),
+        ),
+        |(n, exprs)| Expr::GroupN(n, exprs),
+    )(i)
+}
+
+fn parse_group_named(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((
+                char('('),
+                ws0,
+                alt((keyword("group-named"), keyword("submatch-named"))),
+                ws1,
             )),
-            tuple((digit1, many1(preceded(multispace1, parse_expr)))),
-            tuple((multispace0, char(')'))),
+            tuple((parse_group_name, many1(preceded(ws1, parse_expr)))),
+            tuple((ws0, char(')'))),
         ),
-        |(n, exprs)| Expr::GroupN(n.parse().expect("failed to parse group identifier"), exprs),
+        |(name, exprs)| Expr::GroupNamed(name, exprs),
     )(i)
 }
 
+/// A group name, either bare or quoted, which has to be a valid
+/// identifier.
+fn parse_group_name(i: &str) -> IResult<&str, String> {
+    verify(
+        alt((
+            map(
+                take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_'),
+                str::to_string,
+            ),
+            parse_string_literal,
+        )),
+        |name: &str| {
+            name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        },
+    )(i)
+}
+
+fn parse_group_ref(i: &str) -> IResult<&str, GroupRef> {
+    alt((
+        map(map_res(digit1, str::parse), GroupRef::Number),
+        map(parse_group_name, GroupRef::Name),
+    ))(i)
+}
+
 fn parse_backref(i: &str) -> IResult<&str, Expr> {
     map(
         delimited(
-            tuple((char('('), multispace0, tag("backref"), multispace1)),
+            tuple((char('('), ws0, keyword("backref"), ws1)),
+            parse_group_ref,
+            tuple((ws0, char(')'))),
+        ),
+        Expr::BackRef,
+    )(i)
+}
+
+/// The output formats a raw regexp can be given for.
+const RAW_FORMATS: &[&str] = &[
+    "pcre",
+    "pcre2",
+    "js",
+    "rust",
+    "re2",
+    "python",
+    "posix-ere",
+    "posix-bre",
+];
+
+fn parse_regexp(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword("regexp"), ws1)),
+            separated_list1(
+                ws1,
+                tuple((
+                    opt(terminated(
+                        preceded(
+                            char(':'),
+                            verify(parse_name, |name: &str| RAW_FORMATS.contains(&name)),
+                        ),
+                        ws1,
+                    )),
+                    parse_string_literal,
+                )),
+            ),
+            tuple((ws0, char(')'))),
+        ),
+        Expr::Raw,
+    )(i)
+}
+
+fn parse_if_group(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword("if-group"), ws1)),
+            tuple((
+                parse_group_ref,
+                preceded(ws1, parse_expr),
+                opt(preceded(ws1, parse_expr)),
+            )),
+            tuple((ws0, char(')'))),
+        ),
+        |(group, then, otherwise)| {
+            Expr::IfGroup(group, std::iter::once(then).chain(otherwise).collect())
+        },
+    )(i)
+}
+
+fn parse_recursion(i: &str) -> IResult<&str, Expr> {
+    alt((parse_recurse, parse_call, parse_balanced))(i)
+}
+
+fn parse_recurse(i: &str) -> IResult<&str, Expr> {
+    map(
+        tuple((char('('), ws0, keyword("recurse"), ws0, char(')'))),
+        |_| Expr::Recurse,
+    )(i)
+}
+
+fn parse_call(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword("call"), ws1)),
+            parse_group_ref,
+            tuple((ws0, char(')'))),
+        ),
+        Expr::Call,
+    )(i)
+}
+
+/// Text between balanced delimiters, which may nest. It is a group
+/// which calls itself for each nested pair.
+fn parse_balanced(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword("balanced"), ws1)),
+            verify(
+                separated_pair(parse_delimiter, ws1, parse_delimiter),
+                |(open, close)| !open.is_empty() && !close.is_empty() && open != close,
+            ),
+            tuple((ws0, char(')'))),
+        ),
+        |(open, close)| balanced(open, close),
+    )(i)
+}
+
+fn balanced(open: String, close: String) -> Expr {
+    let mut single_chars = open.chars().chain(close.chars());
+    // Text without either delimiter, or a nested pair.
+    let inner = match (
+        single_chars.next(),
+        single_chars.next(),
+        single_chars.next(),
+    ) {
+        (Some(open), Some(close), None) => Expr::OneOrMorePossessive(vec![Expr::Not(vec![
+            SetItem::Char(open),
+            SetItem::Char(close),
+        ])]),
+        _ => Expr::Seq(vec![
+            Expr::Assertion(Assertion::NegativeLookAhead(vec![Expr::Or(vec![
+                Expr::Atom(Atom::String(open.clone())),
+                Expr::Atom(Atom::String(close.clone())),
+            ])])),
+            Expr::Atom(Atom::AnyChar),
+        ]),
+    };
+    Expr::Group(vec![
+        Expr::Atom(Atom::String(open)),
+        Expr::ZeroOrMore(vec![Expr::Or(vec![
+            inner,
+            Expr::Call(GroupRef::Relative(-1)),
+        ])]),
+        Expr::Atom(Atom::String(close)),
+    ])
+}
+
+fn parse_delimiter(i: &str) -> IResult<&str, String> {
+    alt((parse_string_literal, map(parse_char_literal, String::from)))(i)
+}
+
+/// A name for a definition or parameter.
+fn parse_name(i: &str) -> IResult<&str, String> {
+    map(
+        verify(take_while1(is_symbol_char), |s: &str| {
+            s.starts_with(|c: char| c.is_ascii_alphabetic())
+        }),
+        str::to_string,
+    )(i)
+}
+
+fn parse_definition(i: &str) -> IResult<&str, Definition> {
+    map(
+        separated_pair(
             alt((
-                digit1,
+                map(parse_name, |name| (name, vec![])),
                 delimited(
-                    char('"'),
-                    escaped(none_of("\\\""), '\\', one_of(r#""\"#)),
-                    char('"'),
+                    tuple((char('('), ws0)),
+                    tuple((parse_name, many0(preceded(ws1, parse_name)))),
+                    tuple((ws0, char(')'))),
                 ),
             )),
-            tuple((multispace0, char(')'))),
+            ws1,
+            parse_expr,
         ),
-        |n: &str| Expr::BackRef(n.to_string()),
+        |((name, params), expr)| Definition { name, params, expr },
     )(i)
 }
 
-fn parse_string(i: &str) -> IResult<&str, Atom> {
+fn parse_define(i: &str) -> IResult<&str, Definition> {
+    delimited(
+        tuple((char('('), ws0, keyword("define"), ws1)),
+        parse_definition,
+        tuple((ws0, char(')'))),
+    )(i)
+}
+
+fn parse_let(i: &str) -> IResult<&str, Expr> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword("let"), ws1)),
+            tuple((
+                delimited(
+                    tuple((char('('), ws0)),
+                    separated_list1(
+                        ws1,
+                        delimited(
+                            tuple((char('('), ws0)),
+                            parse_definition,
+                            tuple((ws0, char(')'))),
+                        ),
+                    ),
+                    tuple((ws0, char(')'))),
+                ),
+                many1(preceded(ws1, parse_expr)),
+            )),
+            tuple((ws0, char(')'))),
+        ),
+        |(definitions, mut exprs)| {
+            let body = if exprs.len() == 1 {
+                exprs.remove(0)
+            } else {
+                Expr::Seq(exprs)
+            };
+            Expr::Let(definitions, Box::new(body))
+        },
+    )(i)
+}
+
+/// A reference to a definition. One-character names are parsed as
+/// symbols instead, which are only references if they are bound.
+fn parse_reference(i: &str) -> IResult<&str, Expr> {
+    map(
+        verify(parse_name, |name: &str| name.len() > 1),
+        Expr::Reference,
+    )(i)
+}
+
+/// A bare one-character name, as opposed to a character in a string
+/// or character literal.
+fn parse_symbol(i: &str) -> IResult<&str, char> {
+    map_opt(parse_name, |name| match name.as_bytes() {
+        [c] => Some(char::from(*c)),
+        _ => None,
+    })(i)
+}
+
+/// Whether a name is a built-in class, atom or assertion, which a
+/// reference by that name would parse as instead.
+pub(crate) fn is_builtin_name(name: &str) -> bool {
+    all_consuming(alt((
+        map(parse_class, |_| ()),
+        map(parse_not_newline, |_| ()),
+        map(parse_any_char, |_| ()),
+        map(parse_grapheme, |_| ()),
+        map(parse_newline_sequence, |_| ()),
+        map(parse_assertion, |_| ()),
+    )))(name)
+    .is_ok()
+}
+
+/// The names of built-in forms, which a malformed use of should not be
+/// mistaken for a macro call.
+const FORM_KEYWORDS: &[&str] = &[
+    "and",
+    "any",
+    "atomic",
+    "backref",
+    "balanced",
+    "block",
+    "call",
+    "case-fold",
+    "case-insensitive",
+    "case-sensitive",
+    "category",
+    "char",
+    "char-code",
+    "define",
+    "difference",
+    "dotall",
+    "group",
+    "group-n",
+    "group-named",
+    "if-group",
+    "in",
+    "intersection",
+    "let",
+    "look-ahead",
+    "look-behind",
+    "multiline",
+    "not",
+    "not-look-ahead",
+    "not-look-behind",
+    "one-or-more",
+    "opt",
+    "optional",
+    "or",
+    "range",
+    "recurse",
+    "regexp",
+    "script",
+    "seq",
+    "sequence",
+    "submatch",
+    "submatch-n",
+    "submatch-named",
+    "zero-or-more",
+    "zero-or-one",
+];
+
+fn parse_macro_call(i: &str) -> IResult<&str, Expr> {
+    map(
+        consumed(delimited(
+            tuple((char('('), ws0)),
+            tuple((
+                verify(parse_name, |name: &str| !FORM_KEYWORDS.contains(&name)),
+                many0(preceded(ws1, parse_expr)),
+            )),
+            tuple((ws0, char(')'))),
+        )),
+        |(source, (name, args))| Expr::MacroCall(name, args, source.to_string()),
+    )(i)
+}
+
+fn parse_flag(i: &str) -> IResult<&str, Flag> {
+    alt((
+        map(
+            alt((keyword("case-fold"), keyword("case-insensitive"))),
+            |_| Flag::CaseInsensitive,
+        ),
+        map(keyword("case-sensitive"), |_| Flag::CaseSensitive),
+        map(keyword("multiline"), |_| Flag::Multiline),
+        map(keyword("dotall"), |_| Flag::DotAll),
+    ))(i)
+}
+
+fn parse_scoped(i: &str) -> IResult<&str, Expr> {
     map(
         delimited(
-            char('"'),
-            escaped(none_of("\\\""), '\\', one_of(r#""\"#)),
-            char('"'),
+            tuple((char('('), ws0)),
+            tuple((parse_flag, many1(preceded(ws1, parse_expr)))),
+            tuple((ws0, char(')'))),
         ),
-        |s: &str| Atom::String(s.to_string()),
+        |(flag, exprs)| Expr::Scoped(flag, exprs),
     )(i)
 }
 
+fn parse_string_literal(i: &str) -> IResult<&str, String> {
+    delimited(
+        char('"'),
+        map(
+            opt(escaped_transform(none_of("\\\""), '\\', parse_escape)),
+            Option::unwrap_or_default,
+        ),
+        char('"'),
+    )(i)
+}
+
+/// The character for an escape sequence, after the backslash.
+fn parse_escape(i: &str) -> IResult<&str, char> {
+    alt((
+        value('"', char('"')),
+        value('\\', char('\\')),
+        value('\n', char('n')),
+        value('\t', char('t')),
+        value('\r', char('r')),
+        map_opt(
+            preceded(
+                char('x'),
+                take_while_m_n(2, 2, |c: char| c.is_ascii_hexdigit()),
+            ),
+            hex_char,
+        ),
+        map_opt(
+            delimited(
+                tag("u{"),
+                take_while_m_n(1, 6, |c: char| c.is_ascii_hexdigit()),
+                char('}'),
+            ),
+            hex_char,
+        ),
+    ))(i)
+}
+
+fn hex_char(digits: &str) -> Option<char> {
+    u32::from_str_radix(digits, 16)
+        .ok()
+        .and_then(char::from_u32)
+}
+
+/// A character literal, either `?c` with an optional escape as in
+/// Emacs Lisp, or `#\c` with an optional name as in Common Lisp.
+fn parse_char_literal(i: &str) -> IResult<&str, char> {
+    alt((
+        preceded(
+            char('?'),
+            alt((
+                preceded(char('\\'), alt((parse_escape, anychar))),
+                // A `?` on its own is just a question mark.
+                verify(anychar, |c| !c.is_whitespace() && *c != ')'),
+            )),
+        ),
+        preceded(tag("#\\"), alt((parse_char_name, anychar))),
+    ))(i)
+}
+
+fn parse_char_name(i: &str) -> IResult<&str, char> {
+    alt((
+        value('\n', alt((keyword("newline"), keyword("linefeed")))),
+        value('\r', keyword("return")),
+        value('\t', keyword("tab")),
+        value(' ', keyword("space")),
+        value('\0', alt((keyword("nul"), keyword("null")))),
+        value('\x08', keyword("backspace")),
+        value('\x0C', keyword("page")),
+        value('\x1B', keyword("escape")),
+        value('\x7F', alt((keyword("delete"), keyword("rubout")))),
+    ))(i)
+}
+
+/// A character by its code point, in decimal or as `#xHEX`.
+fn parse_char_code(i: &str) -> IResult<&str, char> {
+    delimited(
+        tuple((char('('), ws0, keyword("char-code"), ws1)),
+        map_opt(
+            alt((
+                map_res(
+                    preceded(tag("#x"), take_while1(|c: char| c.is_ascii_hexdigit())),
+                    |s| u32::from_str_radix(s, 16),
+                ),
+                map_res(digit1, str::parse),
+            )),
+            char::from_u32,
+        ),
+        tuple((ws0, char(')'))),
+    )(i)
+}
+
+fn parse_string(i: &str) -> IResult<&str, Atom> {
+    map(parse_string_literal, Atom::String)(i)
+}
+
 fn parse_char(i: &str) -> IResult<&str, Atom> {
-    map(anychar, Atom::Char)(i)
+    map(parse_bare_char, Atom::Char)(i)
 }
 
-fn parse_atom(i: &str) -> IResult<&str, Atom> {
-    alt((parse_class, parse_string, parse_char))(i)
+/// A character written as itself. Parentheses delimit forms, and `;`
+/// starts a comment, so those have to be written as strings.
+fn parse_bare_char(i: &str) -> IResult<&str, char> {
+    verify(anychar, |c| {
+        !c.is_whitespace() && !matches!(c, '(' | ')' | ';')
+    })(i)
 }
 
-fn parse_atom_expr(i: &str) -> IResult<&str, Expr> {
-    map(alt((parse_class, parse_string, parse_char)), |a| {
-        Expr::Atom(a)
+fn parse_not_newline(i: &str) -> IResult<&str, Atom> {
+    map(alt((keyword("not-newline"), keyword("nonl"))), |_| {
+        Atom::NotNewline
     })(i)
 }
 
+fn parse_grapheme(i: &str) -> IResult<&str, Atom> {
+    map(keyword("grapheme"), |_| Atom::Grapheme)(i)
+}
+
+fn parse_newline_sequence(i: &str) -> IResult<&str, Atom> {
+    map(keyword("newline-sequence"), |_| Atom::NewlineSequence)(i)
+}
+
+fn parse_any_char(i: &str) -> IResult<&str, Atom> {
+    map(alt((keyword("anychar"), keyword("anything"))), |_| {
+        Atom::AnyChar
+    })(i)
+}
+
+fn parse_atom_expr(i: &str) -> IResult<&str, Expr> {
+    alt((
+        map(
+            alt((
+                parse_class,
+                parse_not_newline,
+                parse_any_char,
+                parse_grapheme,
+                parse_newline_sequence,
+                map(parse_char_code, Atom::Char),
+            )),
+            Expr::Atom,
+        ),
+        // After the built-in classes, so that they take precedence
+        parse_macro_call,
+        parse_reference,
+        map(parse_symbol, Expr::Symbol),
+        map(
+            alt((
+                parse_string,
+                map(parse_char_literal, Atom::Char),
+                parse_char,
+            )),
+            Expr::Atom,
+        ),
+    ))(i)
+}
+
 fn parse_line_start(i: &str) -> IResult<&str, Assertion> {
-    map(alt((tag("line-start"), tag("bol"))), |_| {
+    map(alt((keyword("line-start"), keyword("bol"))), |_| {
         Assertion::LineStart
     })(i)
 }
 
 fn parse_line_end(i: &str) -> IResult<&str, Assertion> {
-    map(alt((tag("line-end"), tag("eol"))), |_| Assertion::LineEnd)(i)
+    map(alt((keyword("line-end"), keyword("eol"))), |_| {
+        Assertion::LineEnd
+    })(i)
 }
 
 fn parse_word_boundary(i: &str) -> IResult<&str, Assertion> {
-    map(tag("word-boundary"), |_| Assertion::WordBoundary)(i)
+    map(keyword("word-boundary"), |_| Assertion::WordBoundary)(i)
 }
 
 fn parse_not_word_boundary(i: &str) -> IResult<&str, Assertion> {
-    map(tag("not-word-boundary"), |_| Assertion::NotWordBoundary)(i)
+    map(keyword("not-word-boundary"), |_| Assertion::NotWordBoundary)(i)
+}
+
+fn parse_word_start(i: &str) -> IResult<&str, Assertion> {
+    map(alt((keyword("word-start"), keyword("bow"))), |_| {
+        Assertion::WordStart
+    })(i)
+}
+
+fn parse_word_end(i: &str) -> IResult<&str, Assertion> {
+    map(alt((keyword("word-end"), keyword("eow"))), |_| {
+        Assertion::WordEnd
+    })(i)
+}
+
+fn parse_symbol_start(i: &str) -> IResult<&str, Assertion> {
+    map(keyword("symbol-start"), |_| Assertion::SymbolStart)(i)
+}
+
+fn parse_symbol_end(i: &str) -> IResult<&str, Assertion> {
+    map(keyword("symbol-end"), |_| Assertion::SymbolEnd)(i)
+}
+
+fn parse_string_start(i: &str) -> IResult<&str, Assertion> {
+    map(
+        alt((
+            keyword("string-start"),
+            keyword("buffer-start"),
+            keyword("bos"),
+            keyword("bot"),
+        )),
+        |_| Assertion::StringStart,
+    )(i)
+}
+
+fn parse_string_end_before_newline(i: &str) -> IResult<&str, Assertion> {
+    map(
+        alt((keyword("string-end-or-newline"), keyword("eos-or-newline"))),
+        |_| Assertion::StringEndBeforeNewline,
+    )(i)
+}
+
+fn parse_string_end(i: &str) -> IResult<&str, Assertion> {
+    map(
+        alt((
+            keyword("string-end"),
+            keyword("buffer-end"),
+            keyword("eos"),
+            keyword("eot"),
+        )),
+        |_| Assertion::StringEnd,
+    )(i)
 }
 
 fn parse_assertion(i: &str) -> IResult<&str, Expr> {
@@ -299,72 +1139,325 @@ fn parse_assertion(i: &str) -> IResult<&str, Expr> {
         alt((
             parse_line_start,
             parse_line_end,
+            parse_string_start,
+            parse_string_end_before_newline,
+            parse_string_end,
             parse_word_boundary,
             parse_not_word_boundary,
+            parse_word_start,
+            parse_word_end,
+            parse_symbol_start,
+            parse_symbol_end,
+        )),
+        Expr::Assertion,
+    )(i)
+}
+
+fn parse_look_ahead(i: &str) -> IResult<&str, Assertion> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword("look-ahead"))),
+            many1(preceded(ws1, parse_expr)),
+            tuple((ws0, char(')'))),
+        ),
+        Assertion::LookAhead,
+    )(i)
+}
+
+fn parse_negative_look_ahead(i: &str) -> IResult<&str, Assertion> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword("not-look-ahead"))),
+            many1(preceded(ws1, parse_expr)),
+            tuple((ws0, char(')'))),
+        ),
+        Assertion::NegativeLookAhead,
+    )(i)
+}
+
+fn parse_look_behind(i: &str) -> IResult<&str, Assertion> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword("look-behind"))),
+            many1(preceded(ws1, parse_expr)),
+            tuple((ws0, char(')'))),
+        ),
+        Assertion::LookBehind,
+    )(i)
+}
+
+fn parse_negative_look_behind(i: &str) -> IResult<&str, Assertion> {
+    map(
+        delimited(
+            tuple((char('('), ws0, keyword("not-look-behind"))),
+            many1(preceded(ws1, parse_expr)),
+            tuple((ws0, char(')'))),
+        ),
+        Assertion::NegativeLookBehind,
+    )(i)
+}
+
+fn parse_lookaround(i: &str) -> IResult<&str, Expr> {
+    map(
+        alt((
+            parse_look_ahead,
+            parse_negative_look_ahead,
+            parse_look_behind,
+            parse_negative_look_behind,
         )),
         Expr::Assertion,
     )(i)
 }
 
 fn parse_whitespace(i: &str) -> IResult<&str, CharClass> {
-    map(alt((tag("space"), tag("whitespace"), tag("white"))), |_| {
-        CharClass::Whitespace
-    })(i)
+    map(
+        alt((keyword("space"), keyword("whitespace"), keyword("white"))),
+        |_| CharClass::Whitespace,
+    )(i)
 }
 
 fn parse_alpha(i: &str) -> IResult<&str, CharClass> {
     map(
-        alt((tag("alpha"), tag("alphabetic"), tag("letter"))),
+        alt((keyword("alpha"), keyword("alphabetic"), keyword("letter"))),
         |_| CharClass::Alpha,
     )(i)
 }
 
 fn parse_digit(i: &str) -> IResult<&str, CharClass> {
-    map(alt((tag("digit"), tag("numeric"), tag("num"))), |_| {
-        CharClass::Digit
-    })(i)
+    map(
+        alt((keyword("digit"), keyword("numeric"), keyword("num"))),
+        |_| CharClass::Digit,
+    )(i)
 }
 
 fn parse_alphanum(i: &str) -> IResult<&str, CharClass> {
-    map(alt((tag("alnum"), tag("alphanumeric"))), |_| {
+    map(alt((keyword("alnum"), keyword("alphanumeric"))), |_| {
         CharClass::AlphaNum
     })(i)
 }
 
 fn parse_hex(i: &str) -> IResult<&str, CharClass> {
-    map(alt((tag("xdigit"), tag("hex-digit"), tag("hex"))), |_| {
-        CharClass::Hex
-    })(i)
+    map(
+        alt((keyword("xdigit"), keyword("hex-digit"), keyword("hex"))),
+        |_| CharClass::Hex,
+    )(i)
 }
 
 fn parse_lowercase(i: &str) -> IResult<&str, CharClass> {
-    map(alt((tag("lower"), tag("lower-case"))), |_| {
+    map(alt((keyword("lower"), keyword("lower-case"))), |_| {
         CharClass::LowerCase
     })(i)
 }
 
 fn parse_uppercase(i: &str) -> IResult<&str, CharClass> {
-    map(alt((tag("upper"), tag("upper-case"))), |_| {
+    map(alt((keyword("upper"), keyword("upper-case"))), |_| {
         CharClass::UpperCase
     })(i)
 }
 
 fn parse_word(i: &str) -> IResult<&str, CharClass> {
-    map(alt((tag("word"), tag("wordchar"))), |_| CharClass::Word)(i)
+    map(alt((keyword("word"), keyword("wordchar"))), |_| {
+        CharClass::Word
+    })(i)
 }
 
-fn parse_class(i: &str) -> IResult<&str, Atom> {
+fn parse_not_whitespace(i: &str) -> IResult<&str, CharClass> {
     map(
-        alt((
-            parse_whitespace,
-            parse_alpha,
-            parse_digit,
-            parse_alphanum,
-            parse_hex,
-            parse_lowercase,
-            parse_uppercase,
-            parse_word,
-        )),
-        Atom::CharClass,
+        alt((keyword("not-whitespace"), keyword("not-space"))),
+        |_| CharClass::NotWhitespace,
     )(i)
 }
+
+fn parse_not_digit(i: &str) -> IResult<&str, CharClass> {
+    map(keyword("not-digit"), |_| CharClass::NotDigit)(i)
+}
+
+fn parse_not_word(i: &str) -> IResult<&str, CharClass> {
+    map(alt((keyword("not-wordchar"), keyword("not-word"))), |_| {
+        CharClass::NotWord
+    })(i)
+}
+
+/// Unicode general categories, by long name and abbreviation.
+const CATEGORIES: &[(&str, &str)] = &[
+    ("letter", "L"),
+    ("lowercase-letter", "Ll"),
+    ("uppercase-letter", "Lu"),
+    ("titlecase-letter", "Lt"),
+    ("modifier-letter", "Lm"),
+    ("other-letter", "Lo"),
+    ("mark", "M"),
+    ("nonspacing-mark", "Mn"),
+    ("spacing-mark", "Mc"),
+    ("enclosing-mark", "Me"),
+    ("number", "N"),
+    ("decimal-number", "Nd"),
+    ("letter-number", "Nl"),
+    ("other-number", "No"),
+    ("punctuation", "P"),
+    ("connector-punctuation", "Pc"),
+    ("dash-punctuation", "Pd"),
+    ("open-punctuation", "Ps"),
+    ("close-punctuation", "Pe"),
+    ("initial-punctuation", "Pi"),
+    ("final-punctuation", "Pf"),
+    ("other-punctuation", "Po"),
+    ("symbol", "S"),
+    ("math-symbol", "Sm"),
+    ("currency-symbol", "Sc"),
+    ("modifier-symbol", "Sk"),
+    ("other-symbol", "So"),
+    ("separator", "Z"),
+    ("space-separator", "Zs"),
+    ("line-separator", "Zl"),
+    ("paragraph-separator", "Zp"),
+    ("other", "C"),
+    ("control", "Cc"),
+    ("format", "Cf"),
+    ("private-use", "Co"),
+    ("surrogate", "Cs"),
+    ("unassigned", "Cn"),
+];
+
+/// Unicode scripts, by rx name and Unicode name.
+const SCRIPTS: &[(&str, &str)] = &[
+    ("arabic", "Arabic"),
+    ("armenian", "Armenian"),
+    ("bengali", "Bengali"),
+    ("bopomofo", "Bopomofo"),
+    ("cherokee", "Cherokee"),
+    ("common", "Common"),
+    ("cyrillic", "Cyrillic"),
+    ("devanagari", "Devanagari"),
+    ("ethiopic", "Ethiopic"),
+    ("georgian", "Georgian"),
+    ("greek", "Greek"),
+    ("gujarati", "Gujarati"),
+    ("gurmukhi", "Gurmukhi"),
+    ("han", "Han"),
+    ("hangul", "Hangul"),
+    ("hebrew", "Hebrew"),
+    ("hiragana", "Hiragana"),
+    ("inherited", "Inherited"),
+    ("kannada", "Kannada"),
+    ("katakana", "Katakana"),
+    ("khmer", "Khmer"),
+    ("lao", "Lao"),
+    ("latin", "Latin"),
+    ("malayalam", "Malayalam"),
+    ("mongolian", "Mongolian"),
+    ("myanmar", "Myanmar"),
+    ("sinhala", "Sinhala"),
+    ("syriac", "Syriac"),
+    ("tamil", "Tamil"),
+    ("telugu", "Telugu"),
+    ("thaana", "Thaana"),
+    ("thai", "Thai"),
+    ("tibetan", "Tibetan"),
+];
+
+/// Unicode blocks, by rx name and code point range.
+const BLOCKS: &[(&str, char, char)] = &[
+    ("basic-latin", '\u{0000}', '\u{007F}'),
+    ("latin-1-supplement", '\u{0080}', '\u{00FF}'),
+    ("latin-extended-a", '\u{0100}', '\u{017F}'),
+    ("latin-extended-b", '\u{0180}', '\u{024F}'),
+    ("ipa-extensions", '\u{0250}', '\u{02AF}'),
+    ("combining-diacritical-marks", '\u{0300}', '\u{036F}'),
+    ("greek", '\u{0370}', '\u{03FF}'),
+    ("cyrillic", '\u{0400}', '\u{04FF}'),
+    ("cyrillic-supplement", '\u{0500}', '\u{052F}'),
+    ("armenian", '\u{0530}', '\u{058F}'),
+    ("hebrew", '\u{0590}', '\u{05FF}'),
+    ("arabic", '\u{0600}', '\u{06FF}'),
+    ("devanagari", '\u{0900}', '\u{097F}'),
+    ("thai", '\u{0E00}', '\u{0E7F}'),
+    ("georgian", '\u{10A0}', '\u{10FF}'),
+    ("hangul-jamo", '\u{1100}', '\u{11FF}'),
+    ("general-punctuation", '\u{2000}', '\u{206F}'),
+    ("currency-symbols", '\u{20A0}', '\u{20CF}'),
+    ("arrows", '\u{2190}', '\u{21FF}'),
+    ("mathematical-operators", '\u{2200}', '\u{22FF}'),
+    ("box-drawing", '\u{2500}', '\u{257F}'),
+    ("cjk-symbols-and-punctuation", '\u{3000}', '\u{303F}'),
+    ("hiragana", '\u{3040}', '\u{309F}'),
+    ("katakana", '\u{30A0}', '\u{30FF}'),
+    ("cjk-unified-ideographs", '\u{4E00}', '\u{9FFF}'),
+    ("hangul-syllables", '\u{AC00}', '\u{D7AF}'),
+    ("emoticons", '\u{1F600}', '\u{1F64F}'),
+];
+
+fn parse_property_name(i: &str) -> IResult<&str, &str> {
+    take_while1(|c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_')(i)
+}
+
+fn parse_category(i: &str) -> IResult<&str, CharClass> {
+    map_opt(
+        delimited(
+            tuple((char('('), ws0, keyword("category"), ws1)),
+            parse_property_name,
+            tuple((ws0, char(')'))),
+        ),
+        |name| {
+            CATEGORIES
+                .iter()
+                .find(|(long, short)| *long == name || *short == name)
+                .map(|(_, short)| CharClass::Category(short))
+        },
+    )(i)
+}
+
+fn parse_script(i: &str) -> IResult<&str, CharClass> {
+    map_opt(
+        delimited(
+            tuple((char('('), ws0, keyword("script"), ws1)),
+            parse_property_name,
+            tuple((ws0, char(')'))),
+        ),
+        |name| {
+            SCRIPTS
+                .iter()
+                .find(|(rx_name, unicode_name)| *rx_name == name || *unicode_name == name)
+                .map(|(_, unicode_name)| CharClass::Script(unicode_name))
+        },
+    )(i)
+}
+
+fn parse_block(i: &str) -> IResult<&str, CharClass> {
+    map_opt(
+        delimited(
+            tuple((char('('), ws0, keyword("block"), ws1)),
+            parse_property_name,
+            tuple((ws0, char(')'))),
+        ),
+        |name| {
+            BLOCKS
+                .iter()
+                .find(|(rx_name, _, _)| *rx_name == name)
+                .map(|(_, from, to)| CharClass::Block(*from, *to))
+        },
+    )(i)
+}
+
+fn parse_char_class(i: &str) -> IResult<&str, CharClass> {
+    alt((
+        parse_category,
+        parse_script,
+        parse_block,
+        parse_not_whitespace,
+        parse_not_digit,
+        parse_not_word,
+        parse_whitespace,
+        parse_alpha,
+        parse_digit,
+        parse_alphanum,
+        parse_hex,
+        parse_lowercase,
+        parse_uppercase,
+        parse_word,
+    ))(i)
+}
+
+fn parse_class(i: &str) -> IResult<&str, Atom> {
+    map(parse_char_class, Atom::CharClass)(i)
+}
@@ -0,0 +1,214 @@
+use crate::expr::{Atom, Definition, Expr, RangeEnd, SetItem};
+use crate::parser::is_builtin_name;
+
+/// Replaces all references to definitions and calls of parameterized
+/// definitions with the expressions they stand for, so that the
+/// output formats don't have to deal with them.
+pub fn resolve(expr: &Expr) -> Result<Expr, ResolveError> {
+    resolve_expr(expr, &[], &mut vec![])
+}
+
+/// A set of names visible from an expression.
+#[derive(Clone)]
+enum Scope<'a> {
+    /// Definitions, which are resolved where they are referenced
+    Definitions(&'a [Definition]),
+    /// Macro arguments, which have already been resolved at the call
+    /// site
+    Arguments(Vec<(&'a str, Expr)>),
+}
+
+/// `env` holds the scopes visible from `expr`, innermost last.
+/// `expanding` holds the definitions currently being expanded, by
+/// scope index and name, to detect cycles.
+fn resolve_expr(
+    expr: &Expr,
+    env: &[Scope],
+    expanding: &mut Vec<(usize, String)>,
+) -> Result<Expr, ResolveError> {
+    match expr {
+        Expr::Reference(name) => resolve_call(name, &[], name, env, expanding),
+        // Symbols are only references if the name is bound, otherwise
+        // they are literal characters.
+        Expr::Symbol(c) if lookup(&c.to_string(), env).is_some() => {
+            let name = c.to_string();
+            resolve_call(&name, &[], &name, env, expanding)
+        }
+        Expr::Symbol(c) => Ok(Expr::Atom(Atom::Char(*c))),
+        Expr::MacroCall(name, args, source) => resolve_call(name, args, source, env, expanding),
+        Expr::Any(items) => Ok(Expr::Any(resolve_set_items(items, env, expanding)?)),
+        Expr::Not(items) => Ok(Expr::Not(resolve_set_items(items, env, expanding)?)),
+        Expr::Let(definitions, body) => {
+            // References to these names would never reach the
+            // definition.
+            let mut names = definitions
+                .iter()
+                .flat_map(|d| std::iter::once(&d.name).chain(&d.params));
+            if let Some(name) = names.find(|name| is_builtin_name(name)) {
+                return Err(ResolveError::ShadowsBuiltin(name.clone()));
+            }
+            let mut env = env.to_vec();
+            env.push(Scope::Definitions(definitions));
+            resolve_expr(body, &env, expanding)
+        }
+        _ => {
+            let mut expr = expr.clone();
+            for child in expr.children_mut() {
+                *child = resolve_expr(child, env, expanding)?;
+            }
+            Ok(expr)
+        }
+    }
+}
+
+/// Like outside of sets, symbols in a set are references if the name
+/// is bound. What they stand for must fit in a set.
+fn resolve_set_items(
+    items: &[SetItem],
+    env: &[Scope],
+    expanding: &mut Vec<(usize, String)>,
+) -> Result<Vec<SetItem>, ResolveError> {
+    let mut resolved = vec![];
+    for item in items {
+        match item {
+            SetItem::Symbol(c) if lookup(&c.to_string(), env).is_some() => {
+                let name = c.to_string();
+                match resolve_call(&name, &[], &name, env, expanding)? {
+                    Expr::Any(items) => resolved.extend(items),
+                    Expr::Atom(Atom::CharClass(class)) => resolved.push(SetItem::CharClass(class)),
+                    expr => resolved.push(SetItem::Char(
+                        single_char(&expr).ok_or(ResolveError::NotInSet(name))?,
+                    )),
+                }
+            }
+            SetItem::Symbol(c) => resolved.push(SetItem::Char(*c)),
+            SetItem::SymbolRange(from, to) => {
+                let from = resolve_range_end(from, env, expanding)?;
+                let to = resolve_range_end(to, env, expanding)?;
+                if from > to {
+                    return Err(ResolveError::NotInSet(format!("(range {from} {to})")));
+                }
+                resolved.push(SetItem::Range(from, to));
+            }
+            _ => resolved.push(item.clone()),
+        }
+    }
+    Ok(resolved)
+}
+
+fn resolve_range_end(
+    end: &RangeEnd,
+    env: &[Scope],
+    expanding: &mut Vec<(usize, String)>,
+) -> Result<char, ResolveError> {
+    let c = match end {
+        RangeEnd::Char(c) => return Ok(*c),
+        RangeEnd::Symbol(c) => *c,
+    };
+    let name = c.to_string();
+    if lookup(&name, env).is_none() {
+        return Ok(c);
+    }
+    let expr = resolve_call(&name, &[], &name, env, expanding)?;
+    single_char(&expr).ok_or(ResolveError::NotInSet(name))
+}
+
+fn single_char(expr: &Expr) -> Option<char> {
+    match expr {
+        Expr::Atom(Atom::Char(c)) => Some(*c),
+        Expr::Atom(Atom::String(s)) if s.chars().count() == 1 => s.chars().next(),
+        _ => None,
+    }
+}
+
+fn resolve_call(
+    name: &str,
+    args: &[Expr],
+    source: &str,
+    env: &[Scope],
+    expanding: &mut Vec<(usize, String)>,
+) -> Result<Expr, ResolveError> {
+    let (scope, definition) =
+        lookup(name, env).ok_or_else(|| ResolveError::UnknownName(source.to_string()))?;
+    let definition = match definition {
+        Ok(definition) => definition,
+        Err(argument) if args.is_empty() => return Ok(argument.clone()),
+        Err(_) => return Err(ResolveError::NotAMacro(source.to_string())),
+    };
+    if definition.params.len() != args.len() {
+        return Err(ResolveError::WrongArgumentCount(
+            source.to_string(),
+            definition.params.len(),
+            args.len(),
+        ));
+    }
+    let key = (scope, name.to_string());
+    if expanding.contains(&key) {
+        return Err(ResolveError::CyclicDefinition(source.to_string()));
+    }
+
+    // Arguments are resolved at the call site, and the definition in
+    // the scope it was defined in, which includes its siblings. This
+    // way neither can capture names from the other.
+    let mut arguments = vec![];
+    for (param, arg) in definition.params.iter().zip(args) {
+        arguments.push((param.as_str(), resolve_expr(arg, env, expanding)?));
+    }
+    let mut definition_env = env[..=scope].to_vec();
+    if !arguments.is_empty() {
+        definition_env.push(Scope::Arguments(arguments));
+    }
+
+    expanding.push(key);
+    let resolved = resolve_expr(&definition.expr, &definition_env, expanding);
+    expanding.pop();
+    resolved
+}
+
+/// Finds the innermost definition or argument with the name, and the
+/// index of its scope.
+fn lookup<'a>(name: &str, env: &'a [Scope]) -> Option<(usize, Result<&'a Definition, &'a Expr>)> {
+    env.iter()
+        .enumerate()
+        .rev()
+        .find_map(|(idx, scope)| match scope {
+            Scope::Definitions(definitions) => definitions
+                .iter()
+                .find(|d| d.name == name)
+                .map(|d| (idx, Ok(d))),
+            Scope::Arguments(arguments) => arguments
+                .iter()
+                .find(|(param, _)| *param == name)
+                .map(|(_, arg)| (idx, Err(arg))),
+        })
+}
+
+#[derive(Debug)]
+pub enum ResolveError {
+    UnknownName(String),
+    CyclicDefinition(String),
+    NotAMacro(String),
+    WrongArgumentCount(String, usize, usize),
+    NotInSet(String),
+    ShadowsBuiltin(String),
+}
+
+impl std::error::Error for ResolveError {}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::UnknownName(source) => write!(f, "unknown name: {source}"),
+            Self::CyclicDefinition(source) => write!(f, "definition refers to itself: {source}"),
+            Self::NotAMacro(source) => write!(f, "parameter called with arguments: {source}"),
+            Self::WrongArgumentCount(source, expected, got) => write!(
+                f,
+                "wrong number of arguments, expected {expected}, got {got}: {source}"
+            ),
+            Self::NotInSet(source) => {
+                write!(f, "definition cannot be used in a character set: {source}")
+            }
+            Self::ShadowsBuiltin(name) => write!(f, "definition shadows a built-in name: {name}"),
+        }
+    }
+}
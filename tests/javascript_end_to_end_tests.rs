@@ -6,6 +6,38 @@ fn render(input: &str) -> String {
     convert(input, output).expect("failed to convert")
 }
 
+fn render_dot_all(input: &str) -> String {
+    let output = &JavascriptOutput {
+        dot_all: true,
+        ..Default::default()
+    };
+    convert(input, output).expect("failed to convert")
+}
+
+fn render_multiline(input: &str) -> String {
+    let output = &JavascriptOutput {
+        multiline: true,
+        ..Default::default()
+    };
+    convert(input, output).expect("failed to convert")
+}
+
+fn render_unicode(input: &str) -> String {
+    let output = &JavascriptOutput {
+        unicode: true,
+        ..Default::default()
+    };
+    convert(input, output).expect("failed to convert")
+}
+
+fn render_unicode_sets(input: &str) -> String {
+    let output = &JavascriptOutput {
+        unicode_sets: true,
+        ..Default::default()
+    };
+    convert(input, output).expect("failed to convert")
+}
+
 #[test]
 fn test_char() {
     assert_eq!(render("f"), "f");
@@ -263,11 +295,487 @@ fn test_group_n() {
 
 #[test]
 fn test_backref_numeric() {
-    assert_eq!(render("(backref 5)"), r#"\5"#);
+    assert_eq!(
+        render(r#"(seq (group "a") (backref 1))"#),
+        r#"(?:(a))(?:\1)"#
+    );
 }
 
 #[test]
-#[should_panic(expected = "not supported")]
 fn test_backref_name() {
-    render(r#"(backref "foo")"#);
+    assert_eq!(
+        render(r#"(seq (group-named foo "a") (backref "foo"))"#),
+        r#"(?:(?<foo>a))(?:\k<foo>)"#
+    );
+}
+
+#[test]
+fn test_or_three_strings() {
+    assert_eq!(render(r#"(or "foo" "bar" "baz")"#), "foo|bar|baz");
+}
+
+#[test]
+fn test_look_ahead() {
+    assert_eq!(render(r#"(seq "foo" (look-ahead "bar"))"#), "foo(?=bar)");
+}
+
+#[test]
+fn test_negative_look_ahead() {
+    assert_eq!(
+        render(r#"(seq "foo" (not-look-ahead (or "bar" "baz")))"#),
+        "foo(?!bar|baz)"
+    );
+}
+
+#[test]
+fn test_look_behind() {
+    assert_eq!(render(r#"(seq (look-behind "foo") "bar")"#), "(?<=foo)bar");
+}
+
+#[test]
+fn test_negative_look_behind() {
+    assert_eq!(
+        render(r#"(seq (not-look-behind "a" digit) "bar")"#),
+        r#"(?<!a[\d])bar"#
+    );
+}
+
+#[test]
+fn test_look_behind_alternatives() {
+    assert_eq!(
+        render(r#"(seq (look-behind (or "a" "bc")) "d")"#),
+        "(?<=a|bc)d"
+    );
+}
+
+#[test]
+fn test_look_behind_unbounded() {
+    assert_eq!(render(r#"(look-behind (1+ "a"))"#), "(?<=a+)");
+}
+
+#[test]
+fn test_any_range() {
+    assert_eq!(render(r#"(any (range ?a ?z) "_" digit)"#), r#"[a-z_\d]"#);
+}
+
+#[test]
+fn test_any_range_bare_chars() {
+    assert_eq!(render("(any (range 0 9))"), "[0-9]");
+}
+
+#[test]
+fn test_any_range_string() {
+    assert_eq!(render(r#"(any "a-z" "-")"#), r#"[a-z\-]"#);
+}
+
+#[test]
+fn test_any_escapes_special_chars() {
+    assert_eq!(render(r#"(any "]-^" "\\")"#), r#"[\]-\^\\]"#);
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_any_reversed_range() {
+    render("(any (range ?z ?a))");
+}
+
+#[test]
+fn test_not_any() {
+    assert_eq!(render(r#"(not (any digit space "\""))"#), r#"[^\d\s"]"#);
+}
+
+#[test]
+fn test_not_or() {
+    assert_eq!(
+        render(r#"(not (or digit "_" (range a f)))"#),
+        r#"[^\d_a-f]"#
+    );
+}
+
+#[test]
+fn test_not_digit() {
+    assert_eq!(render("not-digit"), r#"[\D]"#);
+}
+
+#[test]
+fn test_not_space() {
+    assert_eq!(render("(any not-space)"), r#"[\S]"#);
+}
+
+#[test]
+fn test_not_word() {
+    assert_eq!(render("(seq a not-word)"), r#"a[\W]"#);
+}
+
+#[test]
+fn test_category() {
+    assert_eq!(render_unicode("(category letter)"), r#"[\p{L}]"#);
+}
+
+#[test]
+fn test_category_abbreviation() {
+    assert_eq!(render_unicode("(any (category Lu) digit)"), r#"[\p{Lu}\d]"#);
+}
+
+#[test]
+fn test_script() {
+    assert_eq!(render_unicode("(script greek)"), r#"[\p{Script=Greek}]"#);
+}
+
+#[test]
+fn test_block() {
+    assert_eq!(render("(block cyrillic)"), r#"[\u0400-\u04FF]"#);
+}
+
+#[test]
+fn test_block_astral() {
+    assert_eq!(
+        render_unicode("(block emoticons)"),
+        r#"[\u{1F600}-\u{1F64F}]"#
+    );
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_block_astral_without_unicode() {
+    render("(block emoticons)");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_script_without_unicode() {
+    render("(script greek)");
+}
+
+#[test]
+fn test_not_newline() {
+    assert_eq!(render(r#"(seq "a" not-newline)"#), r#"a[^\n]"#);
+}
+
+#[test]
+fn test_anychar() {
+    assert_eq!(render("(0+ anychar)"), r#"[\s\S]*"#);
+}
+
+#[test]
+fn test_anychar_dot_all() {
+    assert_eq!(render_dot_all("(0+ anychar)"), ".*");
+}
+
+#[test]
+fn test_dot_is_literal() {
+    assert_eq!(render("."), r#"\."#);
+}
+
+#[test]
+fn test_string_escapes_metacharacters() {
+    assert_eq!(render(r#""a.b*(c)""#), r#"a\.b\*\(c\)"#);
+}
+
+#[test]
+fn test_string_start() {
+    assert_eq!(render(r#"(seq bos "foo")"#), "^foo");
+}
+
+#[test]
+fn test_string_end() {
+    assert_eq!(render(r#"(seq "foo" string-end)"#), "foo$");
+}
+
+#[test]
+fn test_string_start_multiline() {
+    assert_eq!(render_multiline(r#"(seq bos "foo")"#), r#"(?<![\s\S])foo"#);
+}
+
+#[test]
+fn test_string_end_multiline() {
+    assert_eq!(render_multiline(r#"(seq "foo" eos)"#), r#"foo(?![\s\S])"#);
+}
+
+#[test]
+fn test_string_end_before_newline() {
+    assert_eq!(
+        render(r#"(seq "foo" eos-or-newline)"#),
+        r#"foo(?=\n?(?![\s\S]))"#
+    );
+}
+
+#[test]
+fn test_group_or() {
+    assert_eq!(render(r#"(group (or "a" "b") "c")"#), "((?:a|b)c)");
+}
+
+#[test]
+fn test_group_named() {
+    assert_eq!(
+        render(r#"(group-named year (= 4 digit))"#),
+        r#"(?<year>(?:[\d]){4})"#
+    );
+}
+
+#[test]
+fn test_group_named_quoted() {
+    assert_eq!(render(r#"(group-named "q" (or "'" "\""))"#), r#"(?<q>'|")"#);
+}
+
+#[test]
+fn test_group_named_backref() {
+    assert_eq!(
+        render(r#"(seq (group-named q "'") (0+ alpha) (backref q))"#),
+        r#"(?:(?<q>'))(?:[a-zA-Z]*)(?:\k<q>)"#
+    );
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_group_named_invalid_name() {
+    render(r#"(group-named "2nd" "foo")"#);
+}
+
+#[test]
+fn test_zero_or_one_char() {
+    assert_eq!(render("(opt f)"), "f?");
+}
+
+#[test]
+fn test_zero_or_more_string() {
+    assert_eq!(render(r#"(0+ "ab")"#), "(?:ab)*");
+}
+
+#[test]
+fn test_zero_or_more_or() {
+    assert_eq!(render("(0+ (or a b))"), "(?:a|b)*");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_atomic() {
+    render(r#"(atomic "a")"#);
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_one_or_more_possessive() {
+    render("(++ f)");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_between_possessive() {
+    render("(**+ 2 5 f)");
+}
+
+#[test]
+fn test_case_fold() {
+    assert_eq!(
+        render(r#"(seq "id=" (case-fold "ab1" lower))"#),
+        r#"id=(?:[aA][bB]1[a-zA-Z])"#
+    );
+}
+
+#[test]
+fn test_case_fold_set() {
+    assert_eq!(
+        render(r#"(case-fold (not (any "x" (range ?a ?f))))"#),
+        r#"[^xXa-fA-F]"#
+    );
+}
+
+#[test]
+fn test_case_fold_nested_case_sensitive() {
+    assert_eq!(
+        render(r#"(case-fold "a" (case-sensitive "b"))"#),
+        r#"[aA](?:b)"#
+    );
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_case_fold_backref() {
+    render(r#"(seq (group "a") (case-fold (backref 1)))"#);
+}
+
+#[test]
+fn test_multiline() {
+    assert_eq!(
+        render(r#"(multiline bol "foo" eol)"#),
+        r#"(?<![^\n])foo(?![^\n])"#
+    );
+}
+
+#[test]
+fn test_multiline_with_flag() {
+    assert_eq!(render_multiline(r#"(multiline bol "foo")"#), "^foo");
+}
+
+#[test]
+fn test_zero_or_one_reluctant() {
+    assert_eq!(render(r#"(?? "ab")"#), "(?:ab)??");
+}
+
+#[test]
+fn test_zero_or_one_reluctant_alias() {
+    assert_eq!(render("(opt? f)"), "f??");
+}
+
+#[test]
+fn test_at_least_reluctant() {
+    assert_eq!(render("(>=? 2 f)"), "(?:f){2,}?");
+}
+
+#[test]
+fn test_between_reluctant() {
+    assert_eq!(render("(**? 2 5 f g)"), "(?:fg){2,5}?");
+}
+
+#[test]
+fn test_regexp_for_format() {
+    assert_eq!(
+        render(r#"(* (regexp :pcre "\\p{Xan}" :js "[a-z0-9]"))"#),
+        "(?:[a-z0-9])*"
+    );
+}
+
+#[test]
+#[should_panic(expected = "unbalanced groups")]
+fn test_regexp_unclosed_class() {
+    render(r#"(regexp :js "[(")"#);
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_regexp_case_fold() {
+    render(r#"(case-fold (regexp "a"))"#);
+}
+
+#[test]
+fn test_string_escapes() {
+    assert_eq!(render(r#""a\tb\r\n\x00""#), r#"a\tb\r\n\u0000"#);
+}
+
+#[test]
+fn test_line_separator() {
+    assert_eq!(render(r#""\u{2028}\u{2029}""#), r#"\u2028\u2029"#);
+}
+
+#[test]
+fn test_char_literals() {
+    assert_eq!(
+        render(r#"(seq ?\t #\escape (char-code 65))"#),
+        r#"\t\u001BA"#
+    );
+}
+
+#[test]
+fn test_astral_char_quantified() {
+    assert_eq!(render("(+ (char-code #x1F600))"), "(?:😀)+");
+}
+
+#[test]
+fn test_astral_char_quantified_unicode() {
+    assert_eq!(render_unicode("(+ (char-code #x1F600))"), "😀+");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_if_group() {
+    render("(seq (group a) (if-group 1 b c))");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_recurse() {
+    render("(seq a (opt (recurse)) b)");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_call() {
+    render("(seq (group a) (call 1))");
+}
+
+#[test]
+#[should_panic(expected = "not supported by output format: recursion")]
+fn test_balanced() {
+    render(r#"(balanced "(" ")")"#);
+}
+
+#[test]
+fn test_difference() {
+    assert_eq!(
+        render(r#"(difference alpha (any "aeiou"))"#),
+        "[A-Zb-df-hj-np-tv-z]"
+    );
+}
+
+#[test]
+fn test_difference_whitespace() {
+    assert_eq!(
+        render(r#"(difference space ?\n ?\r (any (range ?\u{2000} ?\u{3000})))"#),
+        r#"[\t\u000B\u000C \u00A0\u1680\uFEFF]"#
+    );
+}
+
+#[test]
+fn test_intersection_unicode_properties() {
+    assert_eq!(
+        render_unicode(r#"(intersection (category letter) (not (any "aeiou")))"#),
+        r#"(?=[\p{L}])[^aeiou]"#
+    );
+}
+
+#[test]
+fn test_difference_unicode_sets() {
+    assert_eq!(
+        render_unicode_sets("(difference word ?_)"),
+        r#"[[\w]--[_]]"#
+    );
+}
+
+#[test]
+fn test_intersection_unicode_sets() {
+    assert_eq!(
+        render_unicode_sets(r#"(intersection (category letter) (not "aeiou") (any "a-m"))"#),
+        r#"[[\p{L}]&&[^aeiou]&&[a-m]]"#
+    );
+}
+
+#[test]
+fn test_set_unicode_sets_escapes() {
+    assert_eq!(render_unicode_sets(r#"(any "(&-")"#), r#"[\(\&\-]"#);
+}
+
+#[test]
+fn test_word_start_end() {
+    assert_eq!(
+        render("(seq bow (+ alpha) eow)"),
+        r#"\b(?=\w)(?:[a-zA-Z]+)\b(?<=\w)"#
+    );
+}
+
+#[test]
+fn test_symbol_start_end() {
+    assert_eq!(
+        render(r#"(seq symbol-start "$el" symbol-end)"#),
+        r#"(?<![\w$])(?=[\w$])\$el(?<=[\w$])(?![\w$])"#
+    );
+}
+
+#[test]
+fn test_grapheme() {
+    assert_eq!(render_unicode("(+ grapheme)"), r#"(?:\r\n|\P{M}\p{M}*)+"#);
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_grapheme_without_unicode() {
+    render("grapheme");
+}
+
+#[test]
+fn test_newline_sequence() {
+    assert_eq!(
+        render("(seq a newline-sequence)"),
+        r#"a(?:\r\n|[\n\v\f\r\u0085\u2028\u2029])"#
+    );
 }
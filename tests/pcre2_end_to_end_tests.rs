@@ -262,10 +262,420 @@ fn test_group_n() {
 
 #[test]
 fn test_backref_numeric() {
-    assert_eq!(render("(backref 5)"), r#"$5"#);
+    assert_eq!(
+        render(r#"(seq (group "a") (backref 1))"#),
+        r#"(?:(a))(?:\1)"#
+    );
 }
 
 #[test]
 fn test_backref_name() {
-    assert_eq!(render(r#"(backref "foo")"#), r#"${foo}"#);
+    assert_eq!(
+        render(r#"(seq (group-named foo "a") (backref "foo"))"#),
+        r#"(?:(?<foo>a))(?:\k<foo>)"#
+    );
+}
+
+#[test]
+fn test_or_three_strings() {
+    assert_eq!(render(r#"(or "foo" "bar" "baz")"#), "foo|bar|baz");
+}
+
+#[test]
+fn test_look_ahead() {
+    assert_eq!(render(r#"(seq "foo" (look-ahead "bar"))"#), "foo(?=bar)");
+}
+
+#[test]
+fn test_negative_look_ahead() {
+    assert_eq!(
+        render(r#"(seq "foo" (not-look-ahead (or "bar" "baz")))"#),
+        "foo(?!bar|baz)"
+    );
+}
+
+#[test]
+fn test_look_behind() {
+    assert_eq!(render(r#"(seq (look-behind "foo") "bar")"#), "(?<=foo)bar");
+}
+
+#[test]
+fn test_negative_look_behind() {
+    assert_eq!(
+        render(r#"(seq (not-look-behind "a" digit) "bar")"#),
+        r#"(?<!a[\d])bar"#
+    );
+}
+
+#[test]
+fn test_look_behind_alternatives() {
+    assert_eq!(
+        render(r#"(seq (look-behind (or "a" "bc")) "d")"#),
+        "(?<=a|bc)d"
+    );
+}
+
+#[test]
+fn test_look_behind_variable_length() {
+    assert_eq!(render(r#"(look-behind (** 1 3 "a"))"#), "(?<=(?:a){1,3})");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_look_behind_unbounded() {
+    render(r#"(look-behind (1+ "a"))"#);
+}
+
+#[test]
+fn test_any_range() {
+    assert_eq!(render(r#"(any (range ?a ?z) "_" digit)"#), r#"[a-z_\d]"#);
+}
+
+#[test]
+fn test_any_range_bare_chars() {
+    assert_eq!(render("(any (range 0 9))"), "[0-9]");
+}
+
+#[test]
+fn test_any_range_string() {
+    assert_eq!(render(r#"(any "a-z" "-")"#), r#"[a-z\-]"#);
+}
+
+#[test]
+fn test_any_escapes_special_chars() {
+    assert_eq!(render(r#"(any "]-^" "\\")"#), r#"[\]-\^\\]"#);
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_any_reversed_range() {
+    render("(any (range ?z ?a))");
+}
+
+#[test]
+fn test_not_any() {
+    assert_eq!(render(r#"(not (any digit space "\""))"#), r#"[^\d\s"]"#);
+}
+
+#[test]
+fn test_not_or() {
+    assert_eq!(
+        render(r#"(not (or digit "_" (range a f)))"#),
+        r#"[^\d_a-f]"#
+    );
+}
+
+#[test]
+fn test_not_digit() {
+    assert_eq!(render("not-digit"), r#"[\D]"#);
+}
+
+#[test]
+fn test_not_space() {
+    assert_eq!(render("(any not-space)"), r#"[\S]"#);
+}
+
+#[test]
+fn test_not_word() {
+    assert_eq!(render("(seq a not-word)"), r#"a[\W]"#);
+}
+
+#[test]
+fn test_category() {
+    assert_eq!(render("(category letter)"), r#"[\p{L}]"#);
+}
+
+#[test]
+fn test_category_abbreviation() {
+    assert_eq!(render("(any (category Lu) digit)"), r#"[\p{Lu}\d]"#);
+}
+
+#[test]
+fn test_script() {
+    assert_eq!(render("(not (script greek))"), r#"[^\p{Greek}]"#);
+}
+
+#[test]
+fn test_block() {
+    assert_eq!(render("(block cyrillic)"), r#"[\x{400}-\x{4FF}]"#);
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_unknown_script() {
+    render("(script klingon)");
+}
+
+#[test]
+fn test_not_newline() {
+    assert_eq!(render(r#"(seq "a" nonl)"#), r#"a\N"#);
+}
+
+#[test]
+fn test_anychar() {
+    assert_eq!(render("(0+ anything)"), "(?s:.)*");
+}
+
+#[test]
+fn test_dot_is_literal() {
+    assert_eq!(render("."), r#"\."#);
+}
+
+#[test]
+fn test_string_escapes_metacharacters() {
+    assert_eq!(render(r#""a.b*(c)""#), r#"a\.b\*\(c\)"#);
+}
+
+#[test]
+fn test_string_start() {
+    assert_eq!(render(r#"(seq bos "foo")"#), r#"\Afoo"#);
+}
+
+#[test]
+fn test_string_end() {
+    assert_eq!(render(r#"(seq "foo" string-end)"#), r#"foo\z"#);
+}
+
+#[test]
+fn test_string_end_before_newline() {
+    assert_eq!(render(r#"(seq "foo" eos-or-newline)"#), r#"foo\Z"#);
+}
+
+#[test]
+fn test_group_or() {
+    assert_eq!(render(r#"(group (or "a" "b") "c")"#), "((?:a|b)c)");
+}
+
+#[test]
+fn test_group_n_or() {
+    assert_eq!(render(r#"(group-n 1 (or "a" "b") "c")"#), "(?<n1>(?:a|b)c)");
+}
+
+#[test]
+fn test_group_named() {
+    assert_eq!(
+        render(r#"(group-named year (= 4 digit))"#),
+        r#"(?<year>(?:[\d]){4})"#
+    );
+}
+
+#[test]
+fn test_group_named_quoted() {
+    assert_eq!(render(r#"(group-named "q" (or "'" "\""))"#), r#"(?<q>'|")"#);
+}
+
+#[test]
+fn test_group_named_backref() {
+    assert_eq!(
+        render(r#"(seq (group-named q "'") (0+ alpha) (backref q))"#),
+        r#"(?:(?<q>'))(?:[a-zA-Z]*)(?:\k<q>)"#
+    );
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_group_named_invalid_name() {
+    render(r#"(group-named "2nd" "foo")"#);
+}
+
+#[test]
+fn test_zero_or_one_char() {
+    assert_eq!(render("(opt f)"), "f?");
+}
+
+#[test]
+fn test_zero_or_more_string() {
+    assert_eq!(render(r#"(0+ "ab")"#), "(?:ab)*");
+}
+
+#[test]
+fn test_zero_or_more_or() {
+    assert_eq!(render("(0+ (or a b))"), "(?:a|b)*");
+}
+
+#[test]
+fn test_atomic() {
+    assert_eq!(render(r#"(atomic (or "ab" "a") "c")"#), "(?>(?:ab|a)c)");
+}
+
+#[test]
+fn test_zero_or_one_possessive() {
+    assert_eq!(render("(?+ f)"), "f?+");
+}
+
+#[test]
+fn test_zero_or_more_possessive() {
+    assert_eq!(render("(*+ digit)"), r#"[\d]*+"#);
+}
+
+#[test]
+fn test_one_or_more_possessive() {
+    assert_eq!(render("(++ f g)"), "(?:fg)++");
+}
+
+#[test]
+fn test_exactly_possessive() {
+    assert_eq!(render("(=+ 2 f)"), "(?:f){2}+");
+}
+
+#[test]
+fn test_at_least_possessive() {
+    assert_eq!(render("(>=+ 2 f)"), "(?:f){2,}+");
+}
+
+#[test]
+fn test_between_possessive() {
+    assert_eq!(render("(**+ 2 5 f)"), "(?:f){2,5}+");
+}
+
+#[test]
+fn test_case_fold() {
+    assert_eq!(
+        render(r#"(seq "id=" (case-fold "abc" digit))"#),
+        r#"id=(?:(?i:abc[\d]))"#
+    );
+}
+
+#[test]
+fn test_case_sensitive() {
+    assert_eq!(render(r#"(case-sensitive "Abc")"#), "(?-i:Abc)");
+}
+
+#[test]
+fn test_multiline() {
+    assert_eq!(render(r#"(multiline bol "foo")"#), "(?m:^foo)");
+}
+
+#[test]
+fn test_dotall() {
+    assert_eq!(render(r#"(dotall (0+ "a"))"#), "(?s:a*)");
+}
+
+#[test]
+fn test_zero_or_one_reluctant() {
+    assert_eq!(render(r#"(?? "ab")"#), "(?:ab)??");
+}
+
+#[test]
+fn test_zero_or_one_reluctant_alias() {
+    assert_eq!(render("(opt? f)"), "f??");
+}
+
+#[test]
+fn test_at_least_reluctant() {
+    assert_eq!(render("(>=? 2 f)"), "(?:f){2,}?");
+}
+
+#[test]
+fn test_between_reluctant() {
+    assert_eq!(render("(**? 2 5 f g)"), "(?:fg){2,5}?");
+}
+
+#[test]
+fn test_regexp_for_format() {
+    assert_eq!(
+        render(r#"(regexp :pcre "\\p{Xan}" :pcre2 "\\p{Xwd}")"#),
+        r#"\p{Xwd}"#
+    );
+}
+
+#[test]
+#[should_panic(expected = "no raw regexp given for output format: pcre2")]
+fn test_regexp_missing_format() {
+    render(r#"(regexp :pcre "\\p{Xan}")"#);
+}
+
+#[test]
+fn test_string_escapes() {
+    assert_eq!(render(r#""a\tb\x07\u{1F600}""#), r#"a\tb\x{7}😀"#);
+}
+
+#[test]
+fn test_char_literals() {
+    assert_eq!(
+        render(r#"(seq ?\n #\nul (char-code #x7F))"#),
+        r#"\n\x{0}\x{7F}"#
+    );
+}
+
+#[test]
+fn test_if_group() {
+    assert_eq!(
+        render(r#"(seq (opt (group-named open "(")) (+ digit) (if-group open ")" "."))"#),
+        r#"(?:(?:(?<open>\())?)(?:[\d]+)(?:(?(<open>)\)|\.))"#
+    );
+}
+
+#[test]
+#[should_panic(expected = "group that does not exist: 1")]
+fn test_if_group_unknown() {
+    render("(if-group 1 a)");
+}
+
+#[test]
+fn test_recurse() {
+    assert_eq!(render("(or a (seq ?< (recurse) ?>))"), "a|<(?:(?R))>");
+}
+
+#[test]
+fn test_call() {
+    assert_eq!(
+        render("(seq (group-named x a) (call x) (call 1))"),
+        "(?:(?<x>a))(?:(?&x))(?:(?1))"
+    );
+}
+
+#[test]
+fn test_balanced() {
+    assert_eq!(
+        render("(balanced ?{ ?})"),
+        r#"(\{(?:(?:(?:[^{}])++|(?-1))*)\})"#
+    );
+}
+
+#[test]
+fn test_difference() {
+    assert_eq!(render("(difference space ?\\n)"), r#"[\t\x{B}-\r ]"#);
+}
+
+#[test]
+fn test_intersection() {
+    assert_eq!(
+        render("(intersection (not (any ?a ?b)) (not ?c))"),
+        "[^a-c]"
+    );
+}
+
+#[test]
+fn test_word_start_end() {
+    assert_eq!(
+        render("(seq bow (+ alpha) eow)"),
+        "[[:<:]](?:[a-zA-Z]+)[[:>:]]"
+    );
+}
+
+#[test]
+fn test_symbol_start_end() {
+    assert_eq!(
+        render(r#"(seq symbol-start "foo" symbol-end)"#),
+        r#"(?<![\w$])(?=[\w$])foo(?<=[\w$])(?![\w$])"#
+    );
+}
+
+#[test]
+fn test_grapheme() {
+    assert_eq!(render("(+ grapheme)"), r#"\X+"#);
+}
+
+#[test]
+fn test_newline_sequence() {
+    assert_eq!(render("(look-behind newline-sequence)"), r#"(?<=\R)"#);
+}
+
+#[test]
+fn test_difference_case_insensitive() {
+    assert_eq!(
+        render(r#"(case-insensitive (difference alpha "a"))"#),
+        "(?i:(?!a)[a-zA-Z])"
+    );
 }
@@ -262,10 +262,891 @@ fn test_group_n() {
 
 #[test]
 fn test_backref_numeric() {
-    assert_eq!(render("(backref 5)"), r#"\5"#);
+    assert_eq!(
+        render(r#"(seq (group "a") (backref 1))"#),
+        r#"(?:(a))(?:\1)"#
+    );
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_backref_overlarge() {
+    render("(backref 99999999999)");
 }
 
 #[test]
 fn test_backref_name() {
-    assert_eq!(render(r#"(backref "foo")"#), r#"${foo}"#);
+    assert_eq!(
+        render(r#"(seq (group-named foo "a") (backref "foo"))"#),
+        r#"(?:(?<foo>a))(?:\k<foo>)"#
+    );
+}
+
+#[test]
+#[should_panic(expected = "group that does not exist: 7")]
+fn test_backref_unknown() {
+    render(r#"(seq (group "a") (backref 7))"#);
+}
+
+#[test]
+#[should_panic(expected = "group that does not exist: nope")]
+fn test_backref_unknown_name() {
+    render("(backref nope)");
+}
+
+#[test]
+fn test_or_three_strings() {
+    assert_eq!(render(r#"(or "foo" "bar" "baz")"#), "foo|bar|baz");
+}
+
+#[test]
+fn test_look_ahead() {
+    assert_eq!(render(r#"(seq "foo" (look-ahead "bar"))"#), "foo(?=bar)");
+}
+
+#[test]
+fn test_negative_look_ahead() {
+    assert_eq!(
+        render(r#"(seq "foo" (not-look-ahead (or "bar" "baz")))"#),
+        "foo(?!bar|baz)"
+    );
+}
+
+#[test]
+fn test_look_behind() {
+    assert_eq!(render(r#"(seq (look-behind "foo") "bar")"#), "(?<=foo)bar");
+}
+
+#[test]
+fn test_negative_look_behind() {
+    assert_eq!(
+        render(r#"(seq (not-look-behind "a" digit) "bar")"#),
+        r#"(?<!a[\d])bar"#
+    );
+}
+
+#[test]
+fn test_look_behind_alternatives() {
+    assert_eq!(
+        render(r#"(seq (look-behind (or "a" "bc")) "d")"#),
+        "(?<=a|bc)d"
+    );
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_look_behind_variable_length() {
+    render(r#"(look-behind (** 1 3 "a"))"#);
+}
+
+#[test]
+fn test_any_range() {
+    assert_eq!(render(r#"(any (range ?a ?z) "_" digit)"#), r#"[a-z_\d]"#);
+}
+
+#[test]
+fn test_any_range_bare_chars() {
+    assert_eq!(render("(any (range 0 9))"), "[0-9]");
+}
+
+#[test]
+fn test_any_range_string() {
+    assert_eq!(render(r#"(any "a-z" "-")"#), r#"[a-z\-]"#);
+}
+
+#[test]
+fn test_any_escapes_special_chars() {
+    assert_eq!(render(r#"(any "]-^" "\\")"#), r#"[\]-\^\\]"#);
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_any_reversed_range() {
+    render("(any (range ?z ?a))");
+}
+
+#[test]
+fn test_not_any() {
+    assert_eq!(render(r#"(not (any digit space "\""))"#), r#"[^\d\s"]"#);
+}
+
+#[test]
+fn test_not_or() {
+    assert_eq!(
+        render(r#"(not (or digit "_" (range a f)))"#),
+        r#"[^\d_a-f]"#
+    );
+}
+
+#[test]
+fn test_not_or_char_literals() {
+    assert_eq!(render("(not (or ?a ?b (char-code 99)))"), "[^abc]");
+}
+
+#[test]
+fn test_not_digit() {
+    assert_eq!(render("not-digit"), r#"[\D]"#);
+}
+
+#[test]
+fn test_not_space() {
+    assert_eq!(render("(any not-space)"), r#"[\S]"#);
+}
+
+#[test]
+fn test_not_word() {
+    assert_eq!(render("(seq a not-word)"), r#"a[\W]"#);
+}
+
+#[test]
+fn test_category() {
+    assert_eq!(render("(category letter)"), r#"[\p{L}]"#);
+}
+
+#[test]
+fn test_category_abbreviation() {
+    assert_eq!(render("(any (category Lu) digit)"), r#"[\p{Lu}\d]"#);
+}
+
+#[test]
+fn test_script() {
+    assert_eq!(render("(not (script greek))"), r#"[^\p{Greek}]"#);
+}
+
+#[test]
+fn test_block() {
+    assert_eq!(render("(block cyrillic)"), r#"[\x{400}-\x{4FF}]"#);
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_unknown_script() {
+    render("(script klingon)");
+}
+
+#[test]
+fn test_not_newline() {
+    assert_eq!(render(r#"(seq "a" nonl)"#), r#"a\N"#);
+}
+
+#[test]
+fn test_anychar() {
+    assert_eq!(render("(0+ anything)"), "(?s:.)*");
+}
+
+#[test]
+fn test_dot_is_literal() {
+    assert_eq!(render("."), r#"\."#);
+}
+
+#[test]
+fn test_string_escapes_metacharacters() {
+    assert_eq!(render(r#""a.b*(c)""#), r#"a\.b\*\(c\)"#);
+}
+
+#[test]
+fn test_string_start() {
+    assert_eq!(render(r#"(seq bos "foo")"#), r#"\Afoo"#);
+}
+
+#[test]
+fn test_string_end() {
+    assert_eq!(render(r#"(seq "foo" string-end)"#), r#"foo\z"#);
+}
+
+#[test]
+fn test_string_end_before_newline() {
+    assert_eq!(render(r#"(seq "foo" eos-or-newline)"#), r#"foo\Z"#);
+}
+
+#[test]
+fn test_group_or() {
+    assert_eq!(render(r#"(group (or "a" "b") "c")"#), "((?:a|b)c)");
+}
+
+#[test]
+fn test_group_n_or() {
+    assert_eq!(render(r#"(group-n 1 (or "a" "b") "c")"#), "(?<n1>(?:a|b)c)");
+}
+
+#[test]
+fn test_group_named() {
+    assert_eq!(
+        render(r#"(group-named year (= 4 digit))"#),
+        r#"(?<year>(?:[\d]){4})"#
+    );
+}
+
+#[test]
+fn test_group_named_quoted() {
+    assert_eq!(render(r#"(group-named "q" (or "'" "\""))"#), r#"(?<q>'|")"#);
+}
+
+#[test]
+fn test_group_named_backref() {
+    assert_eq!(
+        render(r#"(seq (group-named q "'") (0+ alpha) (backref q))"#),
+        r#"(?:(?<q>'))(?:[a-zA-Z]*)(?:\k<q>)"#
+    );
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_group_named_invalid_name() {
+    render(r#"(group-named "2nd" "foo")"#);
+}
+
+#[test]
+fn test_zero_or_one_char() {
+    assert_eq!(render("(opt f)"), "f?");
+}
+
+#[test]
+fn test_zero_or_more_string() {
+    assert_eq!(render(r#"(0+ "ab")"#), "(?:ab)*");
+}
+
+#[test]
+fn test_zero_or_more_or() {
+    assert_eq!(render("(0+ (or a b))"), "(?:a|b)*");
+}
+
+#[test]
+fn test_atomic() {
+    assert_eq!(render(r#"(atomic (or "ab" "a") "c")"#), "(?>(?:ab|a)c)");
+}
+
+#[test]
+fn test_zero_or_one_possessive() {
+    assert_eq!(render("(?+ f)"), "f?+");
+}
+
+#[test]
+fn test_zero_or_more_possessive() {
+    assert_eq!(render("(*+ digit)"), r#"[\d]*+"#);
+}
+
+#[test]
+fn test_one_or_more_possessive() {
+    assert_eq!(render("(++ f g)"), "(?:fg)++");
+}
+
+#[test]
+fn test_exactly_possessive() {
+    assert_eq!(render("(=+ 2 f)"), "(?:f){2}+");
+}
+
+#[test]
+fn test_at_least_possessive() {
+    assert_eq!(render("(>=+ 2 f)"), "(?:f){2,}+");
+}
+
+#[test]
+fn test_between_possessive() {
+    assert_eq!(render("(**+ 2 5 f)"), "(?:f){2,5}+");
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_between_reversed() {
+    render("(** 5 2 f)");
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_between_possessive_reversed() {
+    render("(**+ 5 2 f)");
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_between_reluctant_reversed() {
+    render("(**? 5 2 f)");
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_at_least_reluctant_overlarge() {
+    render("(>=? 99999999999 f)");
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_at_least_possessive_overlarge() {
+    render("(>=+ 99999999999 f)");
+}
+
+#[test]
+fn test_case_fold() {
+    assert_eq!(
+        render(r#"(seq "id=" (case-fold "abc" digit))"#),
+        r#"id=(?:(?i:abc[\d]))"#
+    );
+}
+
+#[test]
+fn test_case_sensitive() {
+    assert_eq!(render(r#"(case-sensitive "Abc")"#), "(?-i:Abc)");
+}
+
+#[test]
+fn test_multiline() {
+    assert_eq!(render(r#"(multiline bol "foo")"#), "(?m:^foo)");
+}
+
+#[test]
+fn test_dotall() {
+    assert_eq!(render(r#"(dotall (0+ "a"))"#), "(?s:a*)");
+}
+
+#[test]
+fn test_zero_or_one_reluctant() {
+    assert_eq!(render(r#"(?? "ab")"#), "(?:ab)??");
+}
+
+#[test]
+fn test_zero_or_one_reluctant_alias() {
+    assert_eq!(render("(opt? f)"), "f??");
+}
+
+#[test]
+fn test_at_least_reluctant() {
+    assert_eq!(render("(>=? 2 f)"), "(?:f){2,}?");
+}
+
+#[test]
+fn test_between_reluctant() {
+    assert_eq!(render("(**? 2 5 f g)"), "(?:fg){2,5}?");
+}
+
+#[test]
+fn test_define() {
+    assert_eq!(
+        render(
+            r#"(define octet (** 1 3 digit)) (define ipv4 (seq octet "." octet)) (seq bos ipv4)"#
+        ),
+        r#"\A(?:(?:[\d]){1,3})\.(?:(?:[\d]){1,3})"#
+    );
+}
+
+#[test]
+fn test_let() {
+    assert_eq!(
+        render(r#"(let ((ab (or "a" "b")) (abab (seq ab ab))) abab "c")"#),
+        "(?:a|b)(?:a|b)c"
+    );
+}
+
+#[test]
+fn test_let_shadowing() {
+    assert_eq!(
+        render(r#"(let ((foo "a") (bar (seq foo))) (let ((foo "b")) bar foo))"#),
+        "ab"
+    );
+}
+
+#[test]
+fn test_name_with_keyword_prefix() {
+    assert_eq!(render(r#"(let ((digits (1+ digit))) digits)"#), r#"[\d]+"#);
+}
+
+#[test]
+#[should_panic(expected = "shadows a built-in name: digit")]
+fn test_define_builtin_name() {
+    render(r#"(define digit "x") digit"#);
+}
+
+#[test]
+#[should_panic(expected = "shadows a built-in name: bol")]
+fn test_macro_param_builtin_name() {
+    render(r#"(let (((f bol) bol)) (f "a"))"#);
+}
+
+#[test]
+#[should_panic(expected = "unknown name")]
+fn test_unknown_name() {
+    render(r#"(let ((foo "a")) (seq foo bar))"#);
+}
+
+#[test]
+#[should_panic(expected = "refers to itself")]
+fn test_cyclic_definition() {
+    render(r#"(let ((foo (seq "a" bar)) (bar (opt foo))) foo)"#);
+}
+
+#[test]
+fn test_macro() {
+    assert_eq!(
+        render(r#"(define (quoted q body) (seq q (*? body) q)) (quoted "'" anything)"#),
+        "'(?:(?s:.)*?)'"
+    );
+}
+
+#[test]
+fn test_macro_in_let() {
+    assert_eq!(
+        render(r#"(let (((list-of sep item) (seq item (0+ sep item)))) (list-of "," digit))"#),
+        r#"[\d](?:(?:,[\d])*)"#
+    );
+}
+
+#[test]
+fn test_macro_hygiene() {
+    assert_eq!(
+        render(r#"(define q "x") (define (wrap body) (seq q body q)) (let ((q "y")) (wrap q))"#),
+        "xyx"
+    );
+}
+
+#[test]
+#[should_panic(expected = r#"expected 2, got 1: (quoted "a")"#)]
+fn test_macro_wrong_arity() {
+    render(r#"(define (quoted q body) (seq q (*? body) q)) (seq "x" (quoted "a"))"#);
+}
+
+#[test]
+#[should_panic(expected = "unknown name: (quoted")]
+fn test_macro_unknown() {
+    render(r#"(quoted "'" anything)"#);
+}
+
+#[test]
+#[should_panic(expected = "refers to itself")]
+fn test_macro_recursive() {
+    render(r#"(define (nest x) (seq x (nest x))) (nest "a")"#);
+}
+
+#[test]
+fn test_malformed_form_is_not_a_macro_call() {
+    let error = convert("(group-n x a)", &PCREOutput::default()).unwrap_err();
+    assert_eq!(error.to_string(), "Failed to parse input");
+}
+
+#[test]
+fn test_macro_in_set() {
+    assert_eq!(render(r#"(define (f c) (any c "x")) (f "q")"#), "[qx]");
+}
+
+#[test]
+fn test_macro_class_in_set() {
+    assert_eq!(
+        render(r#"(define (f c) (not (any c ?_))) (f digit)"#),
+        r#"[^\d_]"#
+    );
+}
+
+#[test]
+fn test_macro_in_range() {
+    assert_eq!(
+        render(r#"(define (upto c) (any (range ?a c))) (upto "f")"#),
+        "[a-f]"
+    );
+}
+
+#[test]
+fn test_bound_symbol_not_in_set_string() {
+    assert_eq!(render(r#"(define x "q") (any "xyz")"#), "[xyz]");
+}
+
+#[test]
+fn test_bound_symbol_not_in_literals() {
+    assert_eq!(render(r#"(define x "q") (seq "xyz" ?x x)"#), "xyzxq");
+}
+
+#[test]
+fn test_bound_symbol_not_in_string_range() {
+    assert_eq!(render(r#"(define x "q") (any "a-x" x)"#), "[a-xq]");
+}
+
+#[test]
+#[should_panic(expected = "cannot be used in a character set: c")]
+fn test_macro_string_in_set() {
+    render(r#"(define (f c) (any c "x")) (f "qr")"#);
+}
+
+#[test]
+fn test_regexp() {
+    assert_eq!(render(r#"(seq "a" (regexp "\\d+"))"#), r#"a(?:\d+)"#);
+}
+
+#[test]
+#[should_panic(expected = "Failed to parse input")]
+fn test_regexp_unknown_format() {
+    render(r#"(regexp :pcer "a")"#);
+}
+
+#[test]
+fn test_regexp_for_format() {
+    assert_eq!(
+        render(r#"(+ (regexp :js "[a-z0-9]" :pcre "\\p{Xan}"))"#),
+        r#"(?:\p{Xan})+"#
+    );
+}
+
+#[test]
+fn test_regexp_fallback() {
+    assert_eq!(render(r#"(regexp :js "[a-z]" "\\w")"#), r#"\w"#);
+}
+
+#[test]
+fn test_regexp_escaped_parens() {
+    assert_eq!(render(r#"(regexp "[(]a\\(")"#), r#"[(]a\("#);
+}
+
+#[test]
+fn test_regexp_posix_class_in_brackets() {
+    assert_eq!(render(r#"(regexp "[[:alpha:](]")"#), "[[:alpha:](]");
+}
+
+#[test]
+fn test_regexp_quoted_paren() {
+    assert_eq!(render(r#"(regexp "\\Q(\\E")"#), r#"\Q(\E"#);
+}
+
+#[test]
+#[should_panic(expected = "unbalanced groups")]
+fn test_regexp_unclosed_posix_class() {
+    render(r#"(regexp "[[:alpha:(]")"#);
+}
+
+#[test]
+#[should_panic(expected = "no raw regexp given for output format: pcre")]
+fn test_regexp_missing_format() {
+    render(r#"(regexp :js "x")"#);
+}
+
+#[test]
+#[should_panic(expected = "unbalanced groups")]
+fn test_regexp_unclosed_group() {
+    render(r#"(regexp "(a")"#);
+}
+
+#[test]
+#[should_panic(expected = "unbalanced groups")]
+fn test_regexp_unopened_group() {
+    render(r#"(seq (regexp "a)") (regexp "(b"))"#);
+}
+
+#[test]
+fn test_line_comments() {
+    assert_eq!(
+        render(
+            "; leading comment
+             (seq ; after the keyword
+               a ; between arguments
+               b) ; trailing comment"
+        ),
+        "ab"
+    );
+}
+
+#[test]
+fn test_line_comment_before_close() {
+    assert_eq!(
+        render(
+            "(seq \"a\"
+               \"b\" ; last argument
+             )"
+        ),
+        "ab"
+    );
+}
+
+#[test]
+fn test_newline_before_close() {
+    assert_eq!(render("(seq \"a\" \"b\"\n)"), "ab");
+}
+
+#[test]
+fn test_block_comment_before_close() {
+    assert_eq!(render(r#"(seq "a" "b" #| c |#)"#), "ab");
+}
+
+#[test]
+fn test_block_comments() {
+    assert_eq!(
+        render("#| leading |# (or#| after the keyword |#a #| multi\nline |# b)"),
+        "a|b"
+    );
+}
+
+#[test]
+fn test_comments_between_definitions() {
+    assert_eq!(
+        render(
+            "(define x \"x\") ; first
+             #| second |# (define y \"y\")
+             (seq x y)"
+        ),
+        "xy"
+    );
+}
+
+#[test]
+fn test_semicolon_in_string() {
+    assert_eq!(render(r#"(seq "a;b" ";")"#), "a;b;");
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_unterminated_block_comment() {
+    render("(seq a #| b)");
+}
+
+#[test]
+fn test_string_escapes() {
+    assert_eq!(render(r#""a\tb\r\n""#), r#"a\tb\r\n"#);
+}
+
+#[test]
+fn test_string_hex_escapes() {
+    assert_eq!(render(r#""\x41\x00\u{1F600}""#), r#"A\x{0}😀"#);
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_string_unknown_escape() {
+    render(r#""\q""#);
+}
+
+#[test]
+fn test_char_literals() {
+    assert_eq!(
+        render(r#"(seq ?a ?\t ?\( #\newline #\b ?)"#),
+        r#"a\t\(\nb\?"#
+    );
+}
+
+#[test]
+fn test_char_literal_quantified() {
+    assert_eq!(render("(+ ?\\x1B)"), r#"\x{1B}+"#);
+}
+
+#[test]
+fn test_char_code() {
+    assert_eq!(render("(seq (char-code #x1F600) (char-code 65))"), "😀A");
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_char_code_surrogate() {
+    render("(char-code #xD800)");
+}
+
+#[test]
+fn test_char_literals_in_set() {
+    assert_eq!(
+        render("(any ?\\n #\\tab (range ?\\x00 ?\\x1F) (char-code 127))"),
+        r#"[\n\t\x{0}-\x{1F}\x{7F}]"#
+    );
+}
+
+#[test]
+fn test_if_group_named() {
+    assert_eq!(
+        render(r#"(seq (opt (group-named open "(")) (+ digit) (if-group open ")"))"#),
+        r#"(?:(?:(?<open>\())?)(?:[\d]+)(?:(?(<open>)\)))"#
+    );
+}
+
+#[test]
+fn test_if_group_else() {
+    assert_eq!(
+        render("(seq (opt (group a)) (if-group 1 b c))"),
+        "(?:(?:(a))?)(?:(?(1)b|c))"
+    );
+}
+
+#[test]
+fn test_if_group_alternatives() {
+    assert_eq!(
+        render("(seq (group a) (if-group 1 (or b c) d))"),
+        "(?:(a))(?:(?(1)(?:b|c)|d))"
+    );
+}
+
+#[test]
+fn test_if_group_forward_reference() {
+    assert_eq!(
+        render("(+ (if-group 1 b a) (opt (group c)))"),
+        "(?:(?:(?(1)b|a))(?:(?:(c))?))+"
+    );
+}
+
+#[test]
+#[should_panic(expected = "group that does not exist: 2")]
+fn test_if_group_unknown_number() {
+    render("(seq (group a) (if-group 2 b))");
+}
+
+#[test]
+#[should_panic(expected = "group that does not exist: open")]
+fn test_if_group_unknown_name() {
+    render("(seq (group a) (if-group open b))");
+}
+
+#[test]
+fn test_recurse() {
+    assert_eq!(render("(seq a (opt (recurse)) b)"), "a(?:(?:(?R))?)b");
+}
+
+#[test]
+fn test_call_number() {
+    assert_eq!(
+        render("(seq (group (+ digit)) (* ?. (call 1)))"),
+        r#"(?:([\d]+))(?:(?:\.(?:(?1)))*)"#
+    );
+}
+
+#[test]
+fn test_call_name() {
+    assert_eq!(
+        render("(seq (call byte) ?. (group-named byte (+ digit)))"),
+        r#"(?:(?&byte))\.(?:(?<byte>[\d]+))"#
+    );
+}
+
+#[test]
+#[should_panic(expected = "group that does not exist: 1")]
+fn test_call_unknown() {
+    render("(call 1)");
+}
+
+#[test]
+fn test_balanced() {
+    assert_eq!(
+        render(r#"(balanced "(" ")")"#),
+        r#"(\((?:(?:(?:[^()])++|(?-1))*)\))"#
+    );
+}
+
+#[test]
+fn test_balanced_strings() {
+    assert_eq!(
+        render(r#"(seq "f" (balanced "<!--" "-->"))"#),
+        "f(?:(<!--(?:(?:(?!<!--|-->)(?s:.)|(?-1))*)-->))"
+    );
+}
+
+#[test]
+#[should_panic(expected = "failed to convert")]
+fn test_balanced_same_delimiters() {
+    render(r#"(balanced "|" "|")"#);
+}
+
+#[test]
+fn test_difference() {
+    assert_eq!(
+        render(r#"(difference alpha (any "aeiou"))"#),
+        "[A-Zb-df-hj-np-tv-z]"
+    );
+}
+
+#[test]
+fn test_difference_word() {
+    assert_eq!(render("(seq (difference word ?_) ?x)"), "[0-9A-Za-z]x");
+}
+
+#[test]
+fn test_difference_negated() {
+    assert_eq!(render(r#"(+ (difference (not "a") "b"))"#), "(?:[^ab])+");
+}
+
+#[test]
+fn test_difference_case_insensitive() {
+    assert_eq!(
+        render(r#"(case-insensitive (difference alpha "a"))"#),
+        "(?i:(?!a)[a-zA-Z])"
+    );
+}
+
+#[test]
+fn test_difference_case_sensitive_in_case_insensitive() {
+    assert_eq!(
+        render(r#"(case-fold (case-sensitive (difference alpha "a")))"#),
+        "(?i:(?-i:[A-Zb-z]))"
+    );
+}
+
+#[test]
+fn test_intersection() {
+    assert_eq!(
+        render(r#"(intersection hex (not digit) (any "a-z"))"#),
+        "[a-f]"
+    );
+}
+
+#[test]
+fn test_intersection_empty() {
+    assert_eq!(render("(intersection digit alpha)"), "(?!)");
+}
+
+#[test]
+fn test_intersection_unicode_properties() {
+    assert_eq!(
+        render(r#"(intersection (category letter) (not (any "aeiou")))"#),
+        r#"(?=[\p{L}])[^aeiou]"#
+    );
+}
+
+#[test]
+fn test_difference_unicode_properties() {
+    assert_eq!(
+        render(r#"(seq ?x (difference (script greek) (any "αω")))"#),
+        r#"x(?![\x{3B1}\x{3C9}])[\p{Greek}]"#
+    );
+}
+
+#[test]
+fn test_set_operation_with_definitions() {
+    assert_eq!(
+        render(r#"(define vowel (any "aeiou")) (difference lower vowel)"#),
+        "[b-df-hj-np-tv-z]"
+    );
+}
+
+#[test]
+#[should_panic(expected = "other than a character set")]
+fn test_set_operation_on_string() {
+    render(r#"(difference alpha "ab")"#);
+}
+
+#[test]
+fn test_word_start_end() {
+    assert_eq!(
+        render("(seq bow (+ alpha) eow)"),
+        r#"\b(?=\w)(?:[a-zA-Z]+)\b(?<=\w)"#
+    );
+}
+
+#[test]
+fn test_word_start_end_long() {
+    assert_eq!(
+        render(r#"(seq word-start "if" word-end)"#),
+        r#"\b(?=\w)if\b(?<=\w)"#
+    );
+}
+
+#[test]
+fn test_symbol_start_end() {
+    assert_eq!(
+        render(r#"(seq symbol-start "foo" symbol-end)"#),
+        r#"(?<![\w$])(?=[\w$])foo(?<=[\w$])(?![\w$])"#
+    );
+}
+
+#[test]
+fn test_grapheme() {
+    assert_eq!(render("(seq ?< (+ grapheme) ?>)"), r#"<(?:\X+)>"#);
+}
+
+#[test]
+fn test_newline_sequence() {
+    assert_eq!(render("(seq (* nonl) newline-sequence)"), r#"(?:\N*)\R"#);
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_newline_sequence_look_behind() {
+    render("(look-behind newline-sequence)");
 }
@@ -0,0 +1,261 @@
+use rx::convert;
+use rx::output::posix_bre::PosixBreOutput;
+
+fn render(input: &str) -> String {
+    let output = &PosixBreOutput::default();
+    convert(input, output).expect("failed to convert")
+}
+
+fn render_gnu(input: &str) -> String {
+    let output = &PosixBreOutput { gnu: true };
+    convert(input, output).expect("failed to convert")
+}
+
+#[test]
+fn test_char() {
+    assert_eq!(render("f"), "f");
+}
+
+#[test]
+fn test_string() {
+    assert_eq!(render(r#""a+b?(c){d}|e.*""#), r#"a+b?(c){d}|e\.\*"#);
+}
+
+#[test]
+fn test_digit() {
+    assert_eq!(render("digit"), "[[:digit:]]");
+}
+
+#[test]
+fn test_not_set() {
+    assert_eq!(render(r#"(not (any "]^[-"))"#), "[^]^[-]");
+}
+
+#[test]
+fn test_zero_or_more() {
+    assert_eq!(render(r#"(* "ab")"#), r#"\(ab\)*"#);
+}
+
+#[test]
+fn test_one_or_more_char() {
+    assert_eq!(render("(+ digit)"), "[[:digit:]][[:digit:]]*");
+}
+
+#[test]
+fn test_one_or_more_string() {
+    assert_eq!(render(r#"(+ "ab")"#), r#"ab\(ab\)*"#);
+}
+
+#[test]
+fn test_one_or_more_group() {
+    assert_eq!(
+        render(r#"(seq (+ (group "ab")) (backref 1))"#),
+        r#"\(ab\)\{1,\}\1"#
+    );
+}
+
+#[test]
+fn test_zero_or_one() {
+    assert_eq!(render(r#"(opt "ab")"#), r#"\(ab\)\{0,1\}"#);
+}
+
+#[test]
+fn test_between() {
+    assert_eq!(render("(** 2 3 digit)"), r#"[[:digit:]]\{2,3\}"#);
+}
+
+#[test]
+fn test_at_least() {
+    assert_eq!(render(r#"(>= 2 "ab")"#), r#"\(ab\)\{2,\}"#);
+}
+
+#[test]
+#[should_panic(expected = "alternation")]
+fn test_or() {
+    render(r#"(or "ab" "c")"#);
+}
+
+#[test]
+#[should_panic(expected = "reluctant quantifiers")]
+fn test_reluctant() {
+    render("(*? a)");
+}
+
+#[test]
+fn test_line_start_end() {
+    assert_eq!(render(r#"(seq bol "a" eol)"#), "^a$");
+}
+
+#[test]
+#[should_panic(expected = "word boundaries")]
+fn test_word_boundary() {
+    render("(seq bow (+ alpha) eow)");
+}
+
+#[test]
+#[should_panic(expected = "lookarounds")]
+fn test_look_ahead() {
+    render("(seq a (look-ahead b))");
+}
+
+#[test]
+fn test_backref() {
+    assert_eq!(render("(seq (group a) (backref 1))"), r#"\(a\)\1"#);
+}
+
+#[test]
+fn test_backref_named() {
+    assert_eq!(
+        render(r#"(seq (group-named q (any "\"'")) (* alpha) (backref q))"#),
+        r#"\(["']\)[[:alpha:]]*\1"#
+    );
+}
+
+#[test]
+fn test_backref_after_grouping() {
+    assert_eq!(
+        render(r#"(seq (= 2 "ab") (group x) (backref 1))"#),
+        r#"\(ab\)\{2\}\(x\)\2"#
+    );
+}
+
+#[test]
+fn test_backref_ninth_group() {
+    assert_eq!(
+        render("(seq (group a) (group b) (group c) (group d) (group e) (group f) (group g) (group h) (group i) (backref 9))"),
+        r#"\(a\)\(b\)\(c\)\(d\)\(e\)\(f\)\(g\)\(h\)\(i\)\9"#
+    );
+}
+
+#[test]
+#[should_panic(expected = "backrefs to groups after the ninth")]
+fn test_backref_tenth_group() {
+    render("(seq (= 2 \"ab\") (group a) (group b) (group c) (group d) (group e) (group f) (group g) (group h) (group i) (backref 9))");
+}
+
+#[test]
+#[should_panic(expected = "reference to a group that does not exist")]
+fn test_backref_missing_group() {
+    render("(seq (group a) (backref 2))");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_group_n() {
+    render("(group-n 2 a)");
+}
+
+#[test]
+fn test_anchors_in_nested_seq() {
+    assert_eq!(render(r#"(seq (seq bol "a") (seq "b" eol))"#), "^ab$");
+}
+
+#[test]
+fn test_anchor_in_group() {
+    assert_eq!(render(r#"(seq "a" (group bol "b"))"#), r#"a\(^b\)"#);
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_anchor_in_middle() {
+    render(r#"(seq "a" bol "b")"#);
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_string_end_in_middle() {
+    render(r#"(seq "a" eos "b")"#);
+}
+
+#[test]
+fn test_one_or_more_anchor() {
+    assert_eq!(render(r#"(+ (seq "a" eol))"#), r#"\(a$\)\{1,\}"#);
+}
+
+#[test]
+fn test_not_newline() {
+    assert_eq!(render("(* nonl)"), ".*");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_newline() {
+    render(r#"(seq "a\n" "b")"#);
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_newline_in_set() {
+    render(r#"(not (any "\n"))"#);
+}
+
+#[test]
+fn test_regexp_for_format() {
+    assert_eq!(
+        render(r#"(seq (regexp :posix-bre "a\\{2\\}") b)"#),
+        r#"\(a\{2\}\)b"#
+    );
+}
+
+#[test]
+fn test_regexp_literal_paren() {
+    assert_eq!(render(r#"(seq (regexp "a(") "b")"#), r#"\(a(\)b"#);
+}
+
+#[test]
+#[should_panic(expected = "unbalanced groups")]
+fn test_regexp_unclosed_group() {
+    render(r#"(regexp "\\(a")"#);
+}
+
+#[test]
+fn test_regexp_groups_numbered() {
+    assert_eq!(
+        render(r#"(seq (group "a") (regexp "\\(b\\)") (group "c") (backref 2))"#),
+        r#"\(a\)\(\(b\)\)\(c\)\4"#
+    );
+}
+
+#[test]
+fn test_gnu_one_or_more() {
+    assert_eq!(render_gnu(r#"(+ "ab")"#), r#"\(ab\)\+"#);
+}
+
+#[test]
+fn test_gnu_zero_or_one() {
+    assert_eq!(render_gnu("(opt digit)"), r#"[[:digit:]]\?"#);
+}
+
+#[test]
+fn test_gnu_or() {
+    assert_eq!(render_gnu(r#"(seq (or "ab" "c") x)"#), r#"\(ab\|c\)x"#);
+}
+
+#[test]
+fn test_gnu_or_in_group() {
+    assert_eq!(
+        render_gnu(r#"(seq (group (or "a" "b")) (backref 1))"#),
+        r#"\(a\|b\)\1"#
+    );
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_gnu_newline_sequence() {
+    render_gnu("newline-sequence");
+}
+
+#[test]
+fn test_gnu_string_start_end() {
+    assert_eq!(render_gnu(r#"(seq bos "a" eos)"#), r#"\`a\'"#);
+}
+
+#[test]
+fn test_gnu_string_end_in_middle() {
+    assert_eq!(render_gnu(r#"(seq "a" eos "b")"#), r#"a\'b"#);
+}
+
+#[test]
+fn test_gnu_word_start_end() {
+    assert_eq!(render_gnu(r#"(seq bow "ab" eow)"#), r#"\<ab\>"#);
+}
@@ -0,0 +1,194 @@
+use rx::convert;
+use rx::output::posix_ere::PosixEreOutput;
+
+fn render(input: &str) -> String {
+    let output = &PosixEreOutput::default();
+    convert(input, output).expect("failed to convert")
+}
+
+#[test]
+fn test_char() {
+    assert_eq!(render("f"), "f");
+}
+
+#[test]
+fn test_string() {
+    assert_eq!(render(r#""a.b+""#), r#"a\.b\+"#);
+}
+
+#[test]
+fn test_closing_brackets() {
+    assert_eq!(render(r#""a]}{""#), r#"a]}\{"#);
+}
+
+#[test]
+fn test_whitespace() {
+    assert_eq!(render("whitespace"), "[[:space:]]");
+}
+
+#[test]
+fn test_digit() {
+    assert_eq!(render("(+ digit)"), "[[:digit:]]+");
+}
+
+#[test]
+fn test_word() {
+    assert_eq!(render("word"), "[[:alnum:]_]");
+}
+
+#[test]
+fn test_not_digit() {
+    assert_eq!(render("not-digit"), "[^[:digit:]]");
+}
+
+#[test]
+fn test_not_set_of_negated_class() {
+    assert_eq!(render("(not not-digit)"), "[[:digit:]]");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_negated_class_in_set() {
+    render(r#"(any "a" not-digit)"#);
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_category() {
+    render("(category letter)");
+}
+
+#[test]
+fn test_set_with_classes() {
+    assert_eq!(render(r#"(any word ".:")"#), "[[:alnum:]_.:]");
+}
+
+#[test]
+fn test_set_special_chars() {
+    assert_eq!(render(r#"(any "]^[-")"#), "[]^[-]");
+}
+
+#[test]
+fn test_not_set_special_chars() {
+    assert_eq!(render(r#"(not (any "]^[-"))"#), "[^]^[-]");
+}
+
+#[test]
+fn test_set_backslash() {
+    assert_eq!(render(r#"(any "\\" "a")"#), r#"[\\a]"#);
+}
+
+#[test]
+fn test_set_only_caret() {
+    assert_eq!(render(r#"(any "^")"#), "[[.^.]]");
+}
+
+#[test]
+fn test_range_ending_in_dash() {
+    assert_eq!(render("(any (range ?! ?-))"), "[!-[.-.]]");
+}
+
+#[test]
+fn test_line_start_end() {
+    assert_eq!(render(r#"(seq bol "a" eol)"#), "^a$");
+}
+
+#[test]
+fn test_or_in_seq() {
+    assert_eq!(render(r#"(seq (or "ab" "c") (+ "xy"))"#), "(ab|c)(xy)+");
+}
+
+#[test]
+fn test_quantified_set_in_seq() {
+    assert_eq!(render(r#"(seq "a" (* (any "\t")))"#), "a[\t]*");
+}
+
+#[test]
+fn test_between() {
+    assert_eq!(render("(** 2 3 digit)"), "[[:digit:]]{2,3}");
+}
+
+#[test]
+fn test_group() {
+    assert_eq!(render("(group a b)"), "(ab)");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_group_named() {
+    render("(group-named key (+ word))");
+}
+
+#[test]
+#[should_panic(expected = "reluctant quantifiers")]
+fn test_reluctant() {
+    render("(*? a)");
+}
+
+#[test]
+#[should_panic(expected = "backrefs")]
+fn test_backref() {
+    render("(seq (group a) (backref 1))");
+}
+
+#[test]
+#[should_panic(expected = "lookarounds")]
+fn test_look_ahead() {
+    render("(seq a (look-ahead b))");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_word_boundary() {
+    render("(seq bow (+ alpha) eow)");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_case_fold() {
+    render(r#"(case-fold "b")"#);
+}
+
+#[test]
+fn test_difference() {
+    assert_eq!(
+        render(r#"(difference (any "a-z") (any "aeiou"))"#),
+        "[b-df-hj-np-tv-z]"
+    );
+}
+
+#[test]
+fn test_not_newline() {
+    assert_eq!(render("(* nonl)"), ".*");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_newline() {
+    render(r#"(seq "a\n" "b")"#);
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_newline_in_set() {
+    render(r#"(any "\n\t")"#);
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_newline_sequence() {
+    render("newline-sequence");
+}
+
+#[test]
+fn test_control_char() {
+    assert_eq!(render(r#""\t""#), "\t");
+}
+
+#[test]
+fn test_regexp_for_format() {
+    assert_eq!(
+        render(r#"(regexp :pcre "\\p{P}" :posix-ere "[[:punct:]]")"#),
+        "[[:punct:]]"
+    );
+}
@@ -0,0 +1,212 @@
+use rx::convert;
+use rx::output::python::PythonOutput;
+
+fn render(input: &str) -> String {
+    let output = &PythonOutput::default();
+    convert(input, output).expect("failed to convert")
+}
+
+fn render_raw_string(input: &str) -> String {
+    let output = &PythonOutput { raw_string: true };
+    convert(input, output).expect("failed to convert")
+}
+
+#[test]
+fn test_char() {
+    assert_eq!(render("f"), "f");
+}
+
+#[test]
+fn test_string() {
+    assert_eq!(render(r#""a.b""#), r#"a\.b"#);
+}
+
+#[test]
+fn test_whitespace() {
+    assert_eq!(render("whitespace"), r#"[\s]"#);
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_category() {
+    render("(category letter)");
+}
+
+#[test]
+fn test_string_start_end() {
+    assert_eq!(render(r#"(: bos "foo" eos)"#), r#"\Afoo\Z"#);
+}
+
+#[test]
+fn test_string_end_before_newline() {
+    assert_eq!(render("eos-or-newline"), r#"(?=\n?\Z)"#);
+}
+
+#[test]
+fn test_between_reluctant() {
+    assert_eq!(render("(**? 2 5 f)"), "(?:f){2,5}?");
+}
+
+#[test]
+fn test_not_set() {
+    assert_eq!(render(r#"(not (any "a-z[&~|"))"#), r#"[^a-z\[\&\~\|]"#);
+}
+
+#[test]
+fn test_not_newline() {
+    assert_eq!(render("(* nonl)"), r#"[^\n]*"#);
+}
+
+#[test]
+fn test_group_n() {
+    assert_eq!(render("(group-n 3 a)"), "(?P<n3>a)");
+}
+
+#[test]
+fn test_group_or() {
+    assert_eq!(render(r#"(group "a" (or "b" "c"))"#), "(a(?:b|c))");
+}
+
+#[test]
+fn test_group_named() {
+    assert_eq!(render("(group-named key (+ word))"), r#"(?P<key>[\w]+)"#);
+}
+
+#[test]
+fn test_backref() {
+    assert_eq!(render("(seq (group a) (backref 1))"), r#"(?:(a))(?:\1)"#);
+}
+
+#[test]
+fn test_backref_named() {
+    assert_eq!(
+        render("(seq (group-named q ?') (backref q))"),
+        "(?:(?P<q>'))(?:(?P=q))"
+    );
+}
+
+#[test]
+fn test_if_group_named() {
+    assert_eq!(
+        render("(seq (group-named x a) (if-group x b c))"),
+        "(?:(?P<x>a))(?:(?(x)b|c))"
+    );
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_recurse() {
+    render("(seq a (opt (recurse)) b)");
+}
+
+#[test]
+fn test_look_behind() {
+    assert_eq!(render(r#"(seq (look-behind "ab") c)"#), "(?<=ab)c");
+}
+
+#[test]
+#[should_panic(expected = "variable-length lookbehind")]
+fn test_look_behind_alternatives() {
+    render(r#"(look-behind (or "ab" "c"))"#);
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_possessive() {
+    render("(*+ a)");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_atomic() {
+    render("(atomic a)");
+}
+
+#[test]
+fn test_case_fold() {
+    assert_eq!(render(r#"(seq "a" (case-fold "b"))"#), "a(?:(?i:b))");
+}
+
+#[test]
+fn test_case_sensitive() {
+    assert_eq!(
+        render(r#"(case-fold "a" (case-sensitive "b"))"#),
+        "(?i:a(?:(?-i:b)))"
+    );
+}
+
+#[test]
+fn test_difference() {
+    assert_eq!(render("(difference word ?_)"), r#"(?!_)[\w]"#);
+}
+
+#[test]
+fn test_difference_case_insensitive() {
+    assert_eq!(
+        render(r#"(case-fold (difference alpha "a"))"#),
+        "(?i:(?!a)[a-zA-Z])"
+    );
+}
+
+#[test]
+fn test_intersection() {
+    assert_eq!(
+        render(r#"(intersection (any "a-z") (not "q"))"#),
+        "[a-pr-z]"
+    );
+}
+
+#[test]
+fn test_newline_sequence() {
+    assert_eq!(
+        render("newline-sequence"),
+        r#"(?:\r\n|[\n\x0b\f\r\x85\u2028\u2029])"#
+    );
+}
+
+#[test]
+fn test_control_char() {
+    assert_eq!(render(r#""\t\x00""#), r#"\t\x00"#);
+}
+
+#[test]
+fn test_non_ascii_set_char() {
+    assert_eq!(render(r#"(any "é😀")"#), r#"[\xe9\U0001f600]"#);
+}
+
+#[test]
+fn test_regexp_for_format() {
+    assert_eq!(
+        render(r#"(regexp :pcre "\\p{Xan}" :python "[^\\W_]")"#),
+        r#"[^\W_]"#
+    );
+}
+
+#[test]
+fn test_raw_string() {
+    assert_eq!(render_raw_string("(+ digit)"), r#"r"[\d]+""#);
+}
+
+#[test]
+fn test_raw_string_quote() {
+    assert_eq!(render_raw_string(r#""say \"hi\"""#), r#"r"say \"hi\"""#);
+}
+
+#[test]
+fn test_raw_string_escaped_quote() {
+    assert_eq!(
+        render_raw_string(r#"(regexp :python "a\\\"")"#),
+        r#"r"a\"""#
+    );
+}
+
+#[test]
+fn test_raw_string_escaped_backslash() {
+    assert_eq!(render_raw_string(r#""a\\""#), r#"r"a\\""#);
+}
+
+#[test]
+#[should_panic(expected = "not supported by output format: recursion")]
+fn test_balanced() {
+    render(r#"(balanced "(" ")")"#);
+}
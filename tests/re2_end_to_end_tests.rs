@@ -0,0 +1,242 @@
+use rx::convert;
+use rx::expr::{Atom, Expr};
+use rx::output::re2::RE2Output;
+use rx::output::Output;
+
+fn render(input: &str) -> String {
+    let output = &RE2Output::default();
+    convert(input, output).expect("failed to convert")
+}
+
+#[test]
+fn test_char() {
+    assert_eq!(render("f"), "f");
+}
+
+#[test]
+fn test_string() {
+    assert_eq!(render(r#""a.b""#), r#"a\.b"#);
+}
+
+#[test]
+fn test_whitespace() {
+    assert_eq!(render("whitespace"), r#"[\s]"#);
+}
+
+#[test]
+fn test_alpha() {
+    assert_eq!(render("alpha"), "[a-zA-Z]");
+}
+
+#[test]
+fn test_digit() {
+    assert_eq!(render("digit"), r#"[\d]"#);
+}
+
+#[test]
+fn test_category() {
+    assert_eq!(render("(category letter)"), r#"[\p{L}]"#);
+}
+
+#[test]
+fn test_script() {
+    assert_eq!(render("(script greek)"), r#"[\p{Greek}]"#);
+}
+
+#[test]
+fn test_line_start() {
+    assert_eq!(render(r#"(: bol "foo")"#), "^foo");
+}
+
+#[test]
+fn test_string_start_end() {
+    assert_eq!(render(r#"(: bos "foo" eos)"#), r#"\Afoo\z"#);
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_string_end_before_newline() {
+    render("eos-or-newline");
+}
+
+#[test]
+fn test_zero_or_more_two_chars() {
+    assert_eq!(render("(0+ f g)"), "(?:fg)*");
+}
+
+#[test]
+fn test_one_or_more_reluctant_char() {
+    assert_eq!(render("(+? f)"), "f+?");
+}
+
+#[test]
+fn test_between_reluctant() {
+    assert_eq!(render("(**? 2 5 f)"), "(?:f){2,5}?");
+}
+
+#[test]
+fn test_or_in_seq() {
+    assert_eq!(render("(seq a (or b c))"), "a(?:b|c)");
+}
+
+#[test]
+fn test_not_set() {
+    assert_eq!(render(r#"(not (any "a-z[^"))"#), r#"[^a-z\[\^]"#);
+}
+
+#[test]
+fn test_not_newline() {
+    assert_eq!(render("(* nonl)"), r#"[^\n]*"#);
+}
+
+#[test]
+fn test_anychar() {
+    assert_eq!(render("anychar"), "(?s:.)");
+}
+
+#[test]
+fn test_group() {
+    assert_eq!(render("(group a b)"), "(ab)");
+}
+
+#[test]
+fn test_group_or() {
+    assert_eq!(render(r#"(group "a" (or "b" "c"))"#), "(a(?:b|c))");
+}
+
+#[test]
+fn test_group_named() {
+    assert_eq!(render("(group-named key (+ word))"), r#"(?P<key>[\w]+)"#);
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_backref() {
+    render("(seq (group a) (backref 1))");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_look_ahead() {
+    render("(seq a (look-ahead b))");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_look_behind() {
+    render("(seq (not-look-behind a) b)");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_possessive() {
+    render("(*+ a)");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_word_start_end() {
+    render("(seq bow (+ alpha) eow)");
+}
+
+#[test]
+fn test_case_fold() {
+    assert_eq!(render(r#"(seq "a" (case-fold "b"))"#), "a(?:(?i:b))");
+}
+
+#[test]
+fn test_difference() {
+    assert_eq!(render("(difference word ?_)"), "[0-9A-Za-z]");
+}
+
+#[test]
+#[should_panic(expected = "set operations in case-insensitive scopes")]
+fn test_difference_case_insensitive() {
+    render(r#"(case-fold (difference alpha "a"))"#);
+}
+
+#[test]
+fn test_difference_whitespace() {
+    assert_eq!(render("(difference space ?\\n)"), r#"[\t\x{C}\r ]"#);
+}
+
+#[test]
+fn test_empty_difference() {
+    assert_eq!(render("(difference digit digit)"), r#"[^\x00-\x{10FFFF}]"#);
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_intersection_category() {
+    render(r#"(intersection (category letter) (not "aeiou"))"#);
+}
+
+#[test]
+fn test_exactly_over_limit() {
+    assert_eq!(render("(= 2500 a)"), "(?:a){1000}(?:a){1000}(?:a){500}");
+}
+
+#[test]
+fn test_between_over_limit() {
+    assert_eq!(
+        render("(** 10 2500 a)"),
+        "(?:a){10,1000}(?:a){0,1000}(?:a){0,500}"
+    );
+}
+
+#[test]
+fn test_at_least_reluctant_over_limit() {
+    assert_eq!(render(r#"(>=? 1500 "ab")"#), "(?:ab){1000}(?:ab){500,}?");
+}
+
+#[test]
+fn test_exactly_at_limit() {
+    assert_eq!(render("(= 1000 a)"), "(?:a){1000}");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_group_over_limit() {
+    render("(** 0 1001 (group a))");
+}
+
+#[test]
+fn test_nested_within_limit() {
+    assert_eq!(render("(= 20 (= 50 a))"), "(?:(?:a){50}){20}");
+}
+
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_nested_over_limit() {
+    render("(= 50 (= 50 a))");
+}
+
+#[test]
+#[should_panic(expected = "ReversedRepetition")]
+fn test_reversed_counts() {
+    let expr = Expr::Between(2500, 900, vec![Expr::Atom(Atom::String("a".to_string()))]);
+    RE2Output::default()
+        .output(&expr)
+        .expect("failed to convert");
+}
+
+#[test]
+fn test_newline_sequence() {
+    assert_eq!(
+        render("newline-sequence"),
+        r#"(?:\r\n|[\n\x0B\f\r\x{85}\x{2028}\x{2029}])"#
+    );
+}
+
+#[test]
+fn test_control_char() {
+    assert_eq!(render(r#""\t\x00""#), r#"\t\x{0}"#);
+}
+
+#[test]
+fn test_regexp_for_format() {
+    assert_eq!(
+        render(r#"(regexp :pcre "\\p{Xan}" :re2 "[\\p{L}\\p{N}]")"#),
+        r#"[\p{L}\p{N}]"#
+    );
+}